@@ -0,0 +1,651 @@
+pub mod cache;
+pub mod cancel;
+pub mod config;
+pub mod limiter;
+pub mod progress;
+pub mod state;
+
+use cache::{CachedChunk, ChunkCache, ChunkCacheMode};
+use cancel::CancellationToken;
+use config::DownloadConfig;
+use limiter::RateLimiter;
+use progress::{Progress, ProgressEvent, ProgressEventKind, ProgressSink};
+use state::DownloadState;
+
+use std::fs;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use log::{debug, warn};
+use reqwest::header;
+use reqwest::{Client, IntoUrl, StatusCode};
+
+use crate::parser::options::{check_chunk_size, DEFAULT_MAX_CHUNK_SIZE};
+use crate::{File, FileChunk, ManifestError, PathStrictness, Result};
+
+/// Delay before the first retry of a failed chunk request.
+const DEFAULT_RETRY_DELAY: Duration = Duration::from_millis(500);
+/// Upper bound for the delay between retries, no matter how many retries were done.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Downloads [files][crate::File] from bundles hosted on a CDN.
+///
+/// Holds a single [`Client`] that is reused for every request, as well as an optional
+/// [`ProgressSink`] that receives [progress events][ProgressEvent], an optional
+/// [`CancellationToken`] that stops the download and an optional [`RateLimiter`] that limits
+/// the bandwidth and the amount of requests. If a [`ChunkCache`] is set, it is consulted before
+/// any chunk is requested, and every downloaded chunk is stored in it.
+///
+/// [`File::download`][crate::File::download] and
+/// [`File::download_with_client`][crate::File::download_with_client] use this internally.
+///
+/// # Examples
+///
+/// See [reporting download progress](index.html#example-reporting-download-progress).
+#[derive(Debug, Clone)]
+pub struct Downloader {
+    client: Client,
+    max_retries: u32,
+    retry_delay: Duration,
+    max_chunk_size: Option<u32>,
+    path_strictness: PathStrictness,
+    progress: Option<Arc<dyn ProgressSink>>,
+    cancellation: Option<CancellationToken>,
+    limiter: Option<RateLimiter>,
+    cache: Option<ChunkCache>,
+}
+
+impl Downloader {
+    /// Creates a new downloader that uses the provided [`Client`].
+    #[must_use]
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            max_retries: 0,
+            retry_delay: DEFAULT_RETRY_DELAY,
+            max_chunk_size: Some(DEFAULT_MAX_CHUNK_SIZE),
+            path_strictness: PathStrictness::Strict,
+            progress: None,
+            cancellation: None,
+            limiter: None,
+            cache: None,
+        }
+    }
+
+    /// Creates a new downloader from the provided [`DownloadConfig`].
+    ///
+    /// # Errors
+    ///
+    /// If building the client fails, it propagates an error from
+    /// [`DownloadConfig::build_client`][crate::DownloadConfig::build_client].
+    pub fn from_config(config: &DownloadConfig) -> Result<Self> {
+        let client = config.build_client()?;
+        let mut downloader = Self::new(client)
+            .with_max_retries(config.max_retries)
+            .with_max_chunk_size(config.max_chunk_size);
+
+        if let Some(retry_delay) = config.retry_delay {
+            downloader = downloader.with_retry_delay(retry_delay);
+        }
+
+        if config.max_bytes_per_second.is_some() || config.max_requests_per_second.is_some() {
+            downloader = downloader.with_rate_limiter(RateLimiter::new(
+                config.max_bytes_per_second,
+                config.max_requests_per_second,
+            ));
+        }
+
+        Ok(downloader)
+    }
+
+    /// Sets how many times a failed chunk request is retried before giving up.
+    ///
+    /// Only errors that are likely to be transient are retried: timeouts, connection errors,
+    /// and responses with a `408`, `429` or `5xx` status code. Other errors (e.g. `404`) fail
+    /// the download straight away. Defaults to 0 (no retries).
+    #[must_use]
+    pub const fn with_max_retries(mut self, max_retries: u32) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Sets the delay before the first retry of a failed chunk request.
+    ///
+    /// The delay is doubled after every retry, up to 30 seconds. Defaults to 500 ms.
+    #[must_use]
+    pub const fn with_retry_delay(mut self, retry_delay: Duration) -> Self {
+        self.retry_delay = retry_delay;
+        self
+    }
+
+    /// Sets the maximum allowed compressed and uncompressed size of a [chunk][FileChunk].
+    ///
+    /// Chunks that are larger are not requested, and the download fails with the error
+    /// [`ChunkSizeLimitExceeded`][crate::ManifestError::ChunkSizeLimitExceeded]. This prevents
+    /// a manifest with forged chunk sizes from causing huge allocations. If [`None`], the size
+    /// is not limited. Defaults to 64 MiB.
+    #[must_use]
+    pub const fn with_max_chunk_size(mut self, max_chunk_size: Option<u32>) -> Self {
+        self.max_chunk_size = max_chunk_size;
+        self
+    }
+
+    /// Sets how strictly [file paths](crate::File::path) are validated before writing files to
+    /// disk.
+    ///
+    /// Defaults to [`PathStrictness::Strict`].
+    #[must_use]
+    pub const fn with_path_strictness(mut self, path_strictness: PathStrictness) -> Self {
+        self.path_strictness = path_strictness;
+        self
+    }
+
+    /// Sets the [`ProgressSink`] that receives [progress events][ProgressEvent].
+    #[must_use]
+    pub fn with_progress<S: ProgressSink + 'static>(mut self, sink: S) -> Self {
+        self.progress = Some(Arc::new(sink));
+        self
+    }
+
+    /// Sets the [`CancellationToken`] used to cancel downloads.
+    ///
+    /// Once the token is cancelled, every download function returns the error
+    /// [`Cancelled`][crate::ManifestError::Cancelled].
+    #[must_use]
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+
+    /// Sets the [`RateLimiter`] applied to every chunk request.
+    ///
+    /// Tokens are taken once per chunk, so retries of a failed request don't take any more. The
+    /// limiter can be cloned and shared between multiple downloaders, in which case the limits
+    /// apply to all of them combined.
+    #[must_use]
+    pub fn with_rate_limiter(mut self, limiter: RateLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Sets the [`ChunkCache`] used to store and look up chunks.
+    ///
+    /// Failing to read from or write to the cache is logged, but does not fail the download.
+    #[must_use]
+    pub fn with_cache(mut self, cache: ChunkCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Returns the [`Client`] used for requests.
+    #[must_use]
+    pub const fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// Downloads a single file and writes its contents to `writer`.
+    ///
+    /// See [`File::download`][crate::File::download] for how the download is done.
+    ///
+    /// # Errors
+    ///
+    /// If a chunk is larger than the [configured limit](Downloader::with_max_chunk_size), the
+    /// error [`ChunkSizeLimitExceeded`][crate::ManifestError::ChunkSizeLimitExceeded] is
+    /// returned.
+    ///
+    /// Otherwise, see [`File::download`][crate::File::download].
+    pub async fn download_file<W: Write + Send, U: IntoUrl + Send>(
+        &self,
+        file: &File,
+        mut writer: W,
+        bundle_url: U,
+    ) -> Result<()> {
+        let mut overall_progress = Progress::default();
+        overall_progress.add_file(file);
+
+        self.download(
+            file,
+            &mut writer,
+            bundle_url.as_str(),
+            &mut overall_progress,
+            0,
+            None,
+        )
+        .await
+    }
+
+    /// Downloads multiple files and writes them to their [paths](crate::File::path), relative to
+    /// `root`.
+    ///
+    /// Missing directories are created, and existing files are overwritten. Files are downloaded
+    /// one after another, and the first error stops the whole download.
+    ///
+    /// On Unix, the [permissions](crate::File::permissions) of each file are applied once it is
+    /// written (see [`FilePermissions::unix_mode`][crate::FilePermissions::unix_mode]).
+    ///
+    /// Paths of all files are validated as a [`ManifestPath`][crate::ManifestPath] (see
+    /// [`with_path_strictness`](Downloader::with_path_strictness)) before anything is written,
+    /// so a file can never be written outside of `root`.
+    ///
+    /// # Errors
+    ///
+    /// If a path is not safe, the error [`UnsafePath`][crate::ManifestError::UnsafePath] is
+    /// returned.
+    ///
+    /// If creating a directory or a file, or setting its permissions fails, the error
+    /// [`IoError`][crate::ManifestError::IoError] is returned.
+    ///
+    /// Otherwise, see [`File::download`][crate::File::download].
+    pub async fn download_files<'a, I, P, U>(&self, files: I, root: P, bundle_url: U) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a File> + Send,
+        P: AsRef<Path> + Send,
+        U: IntoUrl + Send,
+    {
+        self.install(files, root.as_ref(), bundle_url.as_str(), None)
+            .await
+    }
+
+    /// Downloads multiple files like [`download_files`](Downloader::download_files), while
+    /// recording which chunks were written in a [`DownloadState`].
+    ///
+    /// A chunk is only recorded once its size was verified and it was flushed to the file, so
+    /// the state never gets ahead of the data on disk.
+    ///
+    /// Chunks that the state marks as written are skipped, as long as the file on disk is at
+    /// least as large as those chunks. The state is saved after every file, as well as when the
+    /// download fails or gets [cancelled](Downloader::with_cancellation), so calling this
+    /// function again with the same state resumes the download.
+    ///
+    /// # Errors
+    ///
+    /// If saving the state fails, the error [`IoError`][crate::ManifestError::IoError] is
+    /// returned.
+    ///
+    /// Otherwise, see [`download_files`](Downloader::download_files).
+    ///
+    /// # Examples
+    ///
+    /// See [resuming a download](index.html#example-resuming-a-download).
+    pub async fn download_files_resumable<'a, I, P, U>(
+        &self,
+        files: I,
+        root: P,
+        bundle_url: U,
+        state: &mut DownloadState,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a File> + Send,
+        P: AsRef<Path> + Send,
+        U: IntoUrl + Send,
+    {
+        self.install(files, root.as_ref(), bundle_url.as_str(), Some(state))
+            .await
+    }
+
+    async fn install<'a, I>(
+        &self,
+        files: I,
+        root: &Path,
+        bundle_url: &str,
+        mut state: Option<&mut DownloadState>,
+    ) -> Result<()>
+    where
+        I: IntoIterator<Item = &'a File> + Send,
+    {
+        let files: Vec<&File> = files.into_iter().collect();
+        let paths = files
+            .iter()
+            .map(|file| file.manifest_path(self.path_strictness))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut overall_progress = Progress::default();
+        for file in &files {
+            overall_progress.add_file(file);
+        }
+
+        for (file, manifest_path) in files.into_iter().zip(paths) {
+            let path = manifest_path.join_to(root);
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            let mut start_chunk = state.as_deref().map_or(0, |s| s.completed_chunks(file));
+            let mut written_size = file.chunks[..start_chunk]
+                .iter()
+                .map(|c| u64::from(c.uncompressed_size))
+                .sum();
+            if start_chunk > 0 && fs::metadata(&path).map_or(0, |m| m.len()) < written_size {
+                warn!(
+                    "File \"{}\" is smaller than expected, restarting.",
+                    file.path
+                );
+                start_chunk = 0;
+                written_size = 0;
+            }
+
+            let mut output = fs::OpenOptions::new()
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)?;
+            output.set_len(written_size)?;
+            output.seek(SeekFrom::End(0))?;
+            let mut writer = BufWriter::new(output);
+
+            let result = self
+                .download(
+                    file,
+                    &mut writer,
+                    bundle_url,
+                    &mut overall_progress,
+                    start_chunk,
+                    state.as_deref_mut(),
+                )
+                .await;
+            let result = result.and_then(|()| Ok(writer.flush()?));
+            #[cfg(unix)]
+            let result =
+                result.and_then(|()| Ok(fs::set_permissions(&path, file.permissions.to_std())?));
+
+            if let Some(state) = state.as_deref() {
+                if let Err(error) = result {
+                    if let Err(save_error) = state.save() {
+                        warn!("Saving download state failed: {save_error}");
+                    }
+                    return Err(error);
+                }
+                state.save()?;
+            } else {
+                result?;
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn download<W: Write + Send>(
+        &self,
+        file: &File,
+        writer: &mut W,
+        bundle_url: &str,
+        overall_progress: &mut Progress,
+        start_chunk: usize,
+        state: Option<&mut DownloadState>,
+    ) -> Result<()> {
+        let mut file_progress = Progress::default();
+        file_progress.add_file(file);
+
+        let mut tracker = Tracker {
+            sink: self.progress.as_deref(),
+            file,
+            file_progress,
+            overall_progress,
+        };
+
+        let skipped_size: u64 = file.chunks[..start_chunk]
+            .iter()
+            .map(|c| u64::from(c.uncompressed_size))
+            .sum();
+        tracker.update(|p| {
+            p.written_bytes += skipped_size;
+            p.completed_chunks += start_chunk as u64;
+        });
+
+        tracker.report(ProgressEventKind::FileStarted);
+
+        let result = self
+            .download_chunks(&mut tracker, writer, bundle_url, start_chunk, state)
+            .await;
+
+        match result {
+            Ok(()) => {
+                tracker.update(|p| p.completed_files += 1);
+                tracker.report(ProgressEventKind::FileCompleted);
+                Ok(())
+            }
+            Err(error) => {
+                tracker.report(ProgressEventKind::Error { error: &error });
+                Err(error)
+            }
+        }
+    }
+
+    async fn download_chunks<W: Write + Send>(
+        &self,
+        tracker: &mut Tracker<'_>,
+        writer: &mut W,
+        bundle_url: &str,
+        start_chunk: usize,
+        mut state: Option<&mut DownloadState>,
+    ) -> Result<()> {
+        let file = tracker.file;
+
+        for (chunk_index, chunk) in file.chunks.iter().enumerate().skip(start_chunk) {
+            if self
+                .cancellation
+                .as_ref()
+                .is_some_and(CancellationToken::is_cancelled)
+            {
+                return Err(ManifestError::Cancelled);
+            }
+
+            check_chunk_size(
+                self.max_chunk_size,
+                chunk.compressed_size,
+                chunk.uncompressed_size,
+            )?;
+
+            let cached_chunk = match &self.cache {
+                Some(cache) => {
+                    let (cache, cached) = (cache.clone(), *chunk);
+                    blocking(move || cache.get(&cached))
+                        .await
+                        .unwrap_or_else(|error| {
+                            warn!("Reading chunk {} from cache failed: {error}", chunk.id);
+                            None
+                        })
+                }
+                None => None,
+            };
+            if cached_chunk.is_some() {
+                tracker.report(ProgressEventKind::ChunkCached { chunk_index });
+            }
+
+            let decompressed_chunk = match cached_chunk {
+                Some(CachedChunk::Decompressed(decompressed_chunk)) => decompressed_chunk,
+                Some(CachedChunk::Compressed(compressed_chunk)) => {
+                    let decompressed_chunk = Self::decompress(chunk, &compressed_chunk)?;
+                    let bytes = decompressed_chunk.len() as u64;
+                    tracker.update(|p| p.decompressed_bytes += bytes);
+                    tracker.report(ProgressEventKind::ChunkDecompressed { chunk_index, bytes });
+                    decompressed_chunk
+                }
+                None => {
+                    let url = format!("{bundle_url}/{}.bundle", chunk.bundle_id);
+                    let end = u64::from(chunk.offset) + u64::from(chunk.compressed_size);
+                    let Some(last_byte) = end.checked_sub(1) else {
+                        return Err(ManifestError::InvalidChunkRange(chunk.id));
+                    };
+                    let range = format!("bytes={}-{last_byte}", chunk.offset);
+
+                    let compressed_chunk = self
+                        .fetch(tracker, chunk_index, &url, &range, chunk.compressed_size)
+                        .await?;
+                    let bytes = compressed_chunk.len() as u64;
+                    tracker.update(|p| p.downloaded_bytes += bytes);
+                    tracker.report(ProgressEventKind::ChunkDownloaded { chunk_index, bytes });
+
+                    let decompressed_chunk = Self::decompress(chunk, &compressed_chunk)?;
+                    let bytes = decompressed_chunk.len() as u64;
+                    tracker.update(|p| p.decompressed_bytes += bytes);
+                    tracker.report(ProgressEventKind::ChunkDecompressed { chunk_index, bytes });
+
+                    if let Some(cache) = &self.cache {
+                        let data = match cache.mode() {
+                            ChunkCacheMode::Compressed => compressed_chunk,
+                            ChunkCacheMode::Decompressed => decompressed_chunk.clone(),
+                        };
+                        let (cache, cached) = (cache.clone(), *chunk);
+                        if let Err(error) = blocking(move || cache.insert(&cached, &data)).await {
+                            warn!("Writing chunk {} to cache failed: {error}", chunk.id);
+                        }
+                    }
+
+                    decompressed_chunk
+                }
+            };
+            let bytes = decompressed_chunk.len() as u64;
+
+            writer.write_all(&decompressed_chunk)?;
+            // only record chunks that actually reached the file, so that a resumed download
+            // never skips data that was lost in a buffer
+            if let Some(state) = state.as_deref_mut() {
+                writer.flush()?;
+                state.set_completed_chunks(file, chunk_index + 1);
+            }
+
+            tracker.update(|p| {
+                p.written_bytes += bytes;
+                p.completed_chunks += 1;
+            });
+            tracker.report(ProgressEventKind::ChunkCompleted { chunk_index, bytes });
+        }
+
+        // files without chunks should still be recorded as completed
+        if let Some(state) = state {
+            state.set_completed_chunks(file, file.chunks.len());
+        }
+
+        Ok(())
+    }
+
+    fn decompress(chunk: &FileChunk, compressed_chunk: &[u8]) -> Result<Vec<u8>> {
+        debug!("Attempting to convert \"uncompressed_size\" into \"usize\".");
+        let uncompressed_size: usize = chunk.uncompressed_size.try_into()?;
+        debug!("Successfully converted \"uncompressed_size\" into \"usize\".");
+
+        let decompressed_chunk = match zstd::bulk::decompress(compressed_chunk, uncompressed_size) {
+            Ok(result) => result,
+            Err(error) => return Err(ManifestError::ZstdDecompressError(error)),
+        };
+        if decompressed_chunk.len() != uncompressed_size {
+            return Err(ManifestError::UncompressedSizeMismatch(
+                chunk.uncompressed_size,
+                decompressed_chunk.len(),
+            ));
+        }
+
+        Ok(decompressed_chunk)
+    }
+
+    async fn fetch(
+        &self,
+        tracker: &Tracker<'_>,
+        chunk_index: usize,
+        url: &str,
+        range: &str,
+        size: u32,
+    ) -> Result<Vec<u8>> {
+        // tokens are only taken once per chunk, so that retries don't use up the budget
+        if let Some(limiter) = &self.limiter {
+            limiter
+                .acquire(size.into(), self.cancellation.as_ref())
+                .await?;
+        }
+
+        let mut attempt = 0;
+
+        loop {
+            match self.request(url, range).await {
+                Ok(bytes) => return Ok(bytes),
+                Err(error) if attempt < self.max_retries && is_transient(&error) => {
+                    attempt += 1;
+                    let delay = self.retry_delay(attempt);
+                    debug!(
+                        "Request for \"{url}\" failed, retrying in {delay:?} ({attempt}): {error}"
+                    );
+                    tracker.report(ProgressEventKind::Retry {
+                        chunk_index,
+                        attempt,
+                        error: &error,
+                    });
+                    cancel::sleep(delay, self.cancellation.as_ref()).await?;
+                }
+                Err(error) => return Err(error),
+            }
+        }
+    }
+
+    /// Returns the delay before the retry with the provided number (starting at 1).
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        self.retry_delay.saturating_mul(factor).min(MAX_RETRY_DELAY)
+    }
+
+    async fn request(&self, url: &str, range: &str) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .get(url)
+            .header(header::RANGE, range)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.bytes().await?.into())
+    }
+}
+
+/// Runs blocking file system work (e.g. [`ChunkCache`] reads and writes) on the blocking thread
+/// pool, so that it doesn't stall the async runtime.
+async fn blocking<T, F>(f: F) -> Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// Returns whether a failed request is worth retrying.
+fn is_transient(error: &ManifestError) -> bool {
+    let ManifestError::ReqwestError(error) = error else {
+        return false;
+    };
+    error.status().map_or_else(
+        || error.is_timeout() || error.is_connect() || error.is_request() || error.is_body(),
+        |status| {
+            status.is_server_error()
+                || status == StatusCode::REQUEST_TIMEOUT
+                || status == StatusCode::TOO_MANY_REQUESTS
+        },
+    )
+}
+
+struct Tracker<'a> {
+    sink: Option<&'a dyn ProgressSink>,
+    file: &'a File,
+    file_progress: Progress,
+    overall_progress: &'a mut Progress,
+}
+
+impl Tracker<'_> {
+    fn update<F: Fn(&mut Progress)>(&mut self, f: F) {
+        f(&mut self.file_progress);
+        f(self.overall_progress);
+    }
+
+    fn report(&self, kind: ProgressEventKind<'_>) {
+        if let Some(sink) = self.sink {
+            sink.on_event(&ProgressEvent {
+                file: self.file,
+                kind,
+                file_progress: self.file_progress,
+                overall_progress: *self.overall_progress,
+            });
+        }
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+use log::{debug, warn};
+
+use crate::{ChunkId, FileChunk, Result};
+
+const MAGIC: [u8; 4] = *b"RCHK";
+const HEADER_SIZE: usize = 32;
+
+/// Form in which chunks are stored in the [`ChunkCache`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkCacheMode {
+    /// Chunks are stored as they were downloaded (zstd compressed).
+    ///
+    /// Takes less space, but chunks need to be decompressed on every use.
+    #[default]
+    Compressed,
+    /// Chunks are stored after decompression.
+    Decompressed,
+}
+
+impl ChunkCacheMode {
+    const fn to_u8(self) -> u8 {
+        match self {
+            Self::Compressed => 0,
+            Self::Decompressed => 1,
+        }
+    }
+
+    const fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Compressed),
+            1 => Some(Self::Decompressed),
+            _ => None,
+        }
+    }
+}
+
+/// Chunk read from the [`ChunkCache`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CachedChunk {
+    /// Chunk data as it was downloaded (zstd compressed).
+    Compressed(Vec<u8>),
+    /// Chunk data after decompression.
+    Decompressed(Vec<u8>),
+}
+
+/// On-disk cache of downloaded chunks.
+///
+/// Chunks are keyed by their [id](crate::FileChunk::id), which is derived from their contents,
+/// so the same cache can be shared between different manifests (e.g. multiple game versions or
+/// branches).
+///
+/// Every chunk is stored in a separate file, along with a small header containing its size and
+/// checksum, which are verified whenever the chunk is read. Entries that fail verification are
+/// removed. Chunks are written to a temporary file first and then renamed, so a crash never
+/// leaves a partially written entry behind.
+///
+/// The checksum only detects accidental corruption (e.g. a truncated or damaged file). Chunk ids
+/// are not recomputed from the cached data, so the cache directory is trusted: anyone who can
+/// write to it can change the contents of downloaded files.
+///
+/// All methods do blocking file system I/O. [`Downloader`][crate::Downloader] runs them on
+/// tokio's blocking thread pool, other async callers should do the same (e.g. with
+/// [`spawn_blocking`](https://docs.rs/tokio/latest/tokio/task/fn.spawn_blocking.html)).
+///
+/// If a [maximum size](ChunkCache::with_max_size) is set, least recently used chunks are evicted
+/// once the cache grows over it.
+///
+/// Clones of the cache share the same index, so a single cache can be used by multiple
+/// [`Downloader`s][crate::Downloader]. Sharing the same directory between multiple processes
+/// is safe, however their indexes (and size limits) are not synchronized.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rman::Result;
+/// use rman::{ChunkCache, ChunkCacheMode, Downloader};
+///
+/// # fn main() -> Result<()> {
+/// let path = "chunk-cache";
+///   # let path = concat!(env!("OUT_DIR"), "/chunk-cache-example");
+/// let cache = ChunkCache::open(path)?
+///     .with_mode(ChunkCacheMode::Decompressed)
+///     .with_max_size(Some(10 * 1024 * 1024 * 1024));
+///
+/// let downloader = Downloader::new(reqwest::Client::new()).with_cache(cache);
+///   # let _ = downloader;
+///   # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChunkCache {
+    dir: PathBuf,
+    mode: ChunkCacheMode,
+    max_size: Option<u64>,
+    index: Arc<Mutex<Index>>,
+}
+
+impl ChunkCache {
+    /// Opens the cache in the provided directory, creating it if it doesn't exist.
+    ///
+    /// Existing entries are indexed, with their modification time used as the initial order for
+    /// eviction.
+    ///
+    /// # Errors
+    ///
+    /// If creating or reading the directory fails, the error
+    /// [`IoError`][crate::ManifestError::IoError] is returned.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+
+            if path.extension().is_some_and(|e| e == "tmp") {
+                debug!("Removing leftover temporary file \"{}\".", path.display());
+                let _ = fs::remove_file(&path);
+                continue;
+            }
+            let Some(id) = Self::parse_file_name(&path) else {
+                continue;
+            };
+            let metadata = entry.metadata()?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((modified, id, metadata.len()));
+        }
+        entries.sort_unstable();
+
+        let mut index = Index::default();
+        for (_, id, size) in entries {
+            index.insert(id, size);
+        }
+
+        Ok(Self {
+            dir,
+            mode: ChunkCacheMode::default(),
+            max_size: None,
+            index: Arc::new(Mutex::new(index)),
+        })
+    }
+
+    /// Sets the form in which new chunks are stored.
+    ///
+    /// Chunks that were already stored in a different form can still be read.
+    #[must_use]
+    pub const fn with_mode(mut self, mode: ChunkCacheMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the maximum size of the cache in bytes.
+    ///
+    /// If [`None`], the cache grows without a limit.
+    #[must_use]
+    pub fn with_max_size(self, max_size: Option<u64>) -> Self {
+        let cache = Self { max_size, ..self };
+        cache.evict();
+        cache
+    }
+
+    /// Returns the form in which new chunks are stored.
+    #[must_use]
+    pub const fn mode(&self) -> ChunkCacheMode {
+        self.mode
+    }
+
+    /// Returns the directory of the cache.
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the total size of all cached entries in bytes.
+    #[must_use]
+    pub fn size(&self) -> u64 {
+        self.lock().size
+    }
+
+    /// Returns the amount of cached chunks.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns `true` if there are no cached chunks.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Reads a chunk from the cache.
+    ///
+    /// Returns [`None`] if the chunk is not cached, or if the cached entry fails verification (in
+    /// which case it is removed).
+    ///
+    /// # Errors
+    ///
+    /// If reading the entry fails for any reason other than it not existing, the error
+    /// [`IoError`][crate::ManifestError::IoError] is returned.
+    pub fn get(&self, chunk: &FileChunk) -> Result<Option<CachedChunk>> {
+        let path = self.path(chunk.id);
+        let bytes = match fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                self.lock().remove(chunk.id);
+                return Ok(None);
+            }
+            Err(error) => return Err(error.into()),
+        };
+
+        let Some(cached) = Self::verify(chunk, &bytes) else {
+            warn!("Cached chunk {} is corrupted, removing it.", chunk.id);
+            self.remove(chunk.id)?;
+            return Ok(None);
+        };
+
+        // entries written by a different process are not indexed yet
+        self.lock().insert(chunk.id, bytes.len() as u64);
+        Ok(Some(cached))
+    }
+
+    /// Stores a chunk in the cache.
+    ///
+    /// `data` has to be in the form that matches the [mode](ChunkCache::with_mode) of the cache.
+    ///
+    /// # Errors
+    ///
+    /// If writing the entry fails, the error [`IoError`][crate::ManifestError::IoError] is
+    /// returned.
+    pub fn insert(&self, chunk: &FileChunk, data: &[u8]) -> Result<()> {
+        static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+        let mut header = [0u8; HEADER_SIZE];
+        header[..4].copy_from_slice(&MAGIC);
+        header[4] = self.mode.to_u8();
+        header[8..16].copy_from_slice(&chunk.id.get().to_le_bytes());
+        header[16..24].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        header[24..32].copy_from_slice(&checksum(data).to_le_bytes());
+
+        let path = self.path(chunk.id);
+        let tmp_path = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        let result = (|| {
+            let mut file = fs::File::create(&tmp_path)?;
+            file.write_all(&header)?;
+            file.write_all(data)?;
+            file.sync_all()?;
+            fs::rename(&tmp_path, &path)
+        })();
+        if let Err(error) = result {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(error.into());
+        }
+
+        self.lock()
+            .insert(chunk.id, (HEADER_SIZE + data.len()) as u64);
+        self.evict();
+        Ok(())
+    }
+
+    /// Removes a chunk from the cache.
+    ///
+    /// # Errors
+    ///
+    /// If removing the entry fails, the error [`IoError`][crate::ManifestError::IoError] is
+    /// returned.
+    pub fn remove(&self, id: ChunkId) -> Result<()> {
+        self.lock().remove(id);
+        match fs::remove_file(self.path(id)) {
+            Err(error) if error.kind() != ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Removes all chunks from the cache.
+    ///
+    /// # Errors
+    ///
+    /// If removing an entry fails, the error [`IoError`][crate::ManifestError::IoError] is
+    /// returned.
+    pub fn clear(&self) -> Result<()> {
+        let ids: Vec<ChunkId> = self.lock().entries.keys().copied().collect();
+        for id in ids {
+            self.remove(id)?;
+        }
+        Ok(())
+    }
+
+    fn evict(&self) {
+        let Some(max_size) = self.max_size else {
+            return;
+        };
+
+        loop {
+            let Some(id) = self.lock().least_recently_used(max_size) else {
+                return;
+            };
+            debug!("Evicting chunk {id} from the cache.");
+            if let Err(error) = self.remove(id) {
+                warn!("Evicting chunk {id} failed: {error}");
+            }
+        }
+    }
+
+    fn verify(chunk: &FileChunk, bytes: &[u8]) -> Option<CachedChunk> {
+        let (header, data) = bytes.split_at_checked(HEADER_SIZE)?;
+
+        let read_u64 = |range: std::ops::Range<usize>| {
+            header.get(range)?.try_into().ok().map(u64::from_le_bytes)
+        };
+
+        if header[..4] != MAGIC
+            || read_u64(8..16)? != u64::from_le_bytes(chunk.id.get().to_le_bytes())
+            || read_u64(16..24)? != data.len() as u64
+            || read_u64(24..32)? != checksum(data)
+        {
+            return None;
+        }
+
+        let mode = ChunkCacheMode::from_u8(header[4])?;
+        let expected_size = match mode {
+            ChunkCacheMode::Compressed => chunk.compressed_size,
+            ChunkCacheMode::Decompressed => chunk.uncompressed_size,
+        };
+        if data.len() as u64 != u64::from(expected_size) {
+            return None;
+        }
+
+        let data = data.to_vec();
+        match mode {
+            ChunkCacheMode::Compressed => Some(CachedChunk::Compressed(data)),
+            ChunkCacheMode::Decompressed => Some(CachedChunk::Decompressed(data)),
+        }
+    }
+
+    fn path(&self, id: ChunkId) -> PathBuf {
+        self.dir.join(format!("{id}.chunk"))
+    }
+
+    fn parse_file_name(path: &Path) -> Option<ChunkId> {
+        if path.extension()? != "chunk" {
+            return None;
+        }
+        path.file_stem()?.to_str()?.parse().ok()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Index> {
+        self.index.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// In-memory index of cached entries, ordered by last use.
+#[derive(Debug, Default)]
+struct Index {
+    entries: HashMap<ChunkId, (u64, u64)>,
+    order: BTreeMap<u64, ChunkId>,
+    clock: u64,
+    size: u64,
+}
+
+impl Index {
+    fn insert(&mut self, id: ChunkId, size: u64) {
+        self.remove(id);
+        self.clock += 1;
+        self.entries.insert(id, (size, self.clock));
+        self.order.insert(self.clock, id);
+        self.size += size;
+    }
+
+    fn remove(&mut self, id: ChunkId) {
+        if let Some((size, last_used)) = self.entries.remove(&id) {
+            self.order.remove(&last_used);
+            self.size -= size;
+        }
+    }
+
+    fn least_recently_used(&self, max_size: u64) -> Option<ChunkId> {
+        if self.size <= max_size {
+            return None;
+        }
+        self.order.values().next().copied()
+    }
+}
+
+/// 64-bit FNV-1a hash, used to detect corrupted entries.
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xCBF2_9CE4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01B3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    fn chunk(id: i64, size: u32) -> FileChunk {
+        FileChunk {
+            id: ChunkId(id),
+            compressed_size: size,
+            uncompressed_size: size,
+            ..Default::default()
+        }
+    }
+
+    fn open(name: &str) -> ChunkCache {
+        let dir = env::temp_dir().join(name);
+        let _ = fs::remove_dir_all(&dir);
+        ChunkCache::open(dir).unwrap()
+    }
+
+    #[test]
+    fn should_return_inserted_chunk() {
+        let cache = open("rman-should-return-inserted-chunk");
+        cache.insert(&chunk(1, 4), b"TEST").unwrap();
+
+        let cached = cache.get(&chunk(1, 4)).unwrap();
+        assert_eq!(cached, Some(CachedChunk::Compressed(b"TEST".to_vec())));
+        assert_eq!(cache.get(&chunk(2, 4)).unwrap(), None);
+
+        let reopened = ChunkCache::open(cache.dir()).unwrap();
+        assert_eq!(reopened.len(), 1);
+        assert_eq!(reopened.size(), cache.size());
+
+        cache.clear().unwrap();
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn should_remove_corrupted_chunk() {
+        let cache = open("rman-should-remove-corrupted-chunk");
+        cache.insert(&chunk(1, 4), b"TEST").unwrap();
+
+        let path = cache.path(ChunkId(1));
+        let mut bytes = fs::read(&path).unwrap();
+        *bytes.last_mut().unwrap() = b'X';
+        fs::write(&path, bytes).unwrap();
+
+        assert_eq!(cache.get(&chunk(1, 4)).unwrap(), None);
+        assert!(!path.exists());
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn should_evict_least_recently_used_chunk() {
+        let entry_size = (HEADER_SIZE + 4) as u64;
+        let cache =
+            open("rman-should-evict-least-recently-used-chunk").with_max_size(Some(entry_size * 2));
+
+        cache.insert(&chunk(1, 4), b"TEST").unwrap();
+        cache.insert(&chunk(2, 4), b"TEST").unwrap();
+        cache.get(&chunk(1, 4)).unwrap();
+        cache.insert(&chunk(3, 4), b"TEST").unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&chunk(1, 4)).unwrap().is_some());
+        assert!(cache.get(&chunk(2, 4)).unwrap().is_none());
+        assert!(cache.get(&chunk(3, 4)).unwrap().is_some());
+
+        cache.clear().unwrap();
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+
+use crate::{ManifestError, Result};
+
+/// Token used to cancel a running download.
+///
+/// Cloning the token is cheap, and all clones share the same state, so one clone can be given to
+/// a [`Downloader`][crate::Downloader], while another is used to
+/// [cancel](CancellationToken::cancel) the download from a different task or thread.
+///
+/// Cancellation is checked before each chunk is requested, so the chunk that is currently being
+/// downloaded is still fully written before the download stops. Waiting for a
+/// [`RateLimiter`][crate::RateLimiter] or for the next retry is interrupted straight away.
+#[derive(Debug, Default, Clone)]
+pub struct CancellationToken {
+    inner: Arc<Inner>,
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    /// Creates a new token that is not cancelled.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels all downloads using this token (or any of its clones).
+    pub fn cancel(&self) {
+        self.inner.cancelled.store(true, Ordering::SeqCst);
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Returns `true` if [`cancel`](CancellationToken::cancel) was called.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.inner.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Waits until [`cancel`](CancellationToken::cancel) is called.
+    ///
+    /// Returns immediately if the token is already cancelled.
+    pub async fn cancelled(&self) {
+        loop {
+            // register before checking, so that a cancel in between is not missed
+            let notified = self.inner.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// Sleeps for `duration`, unless the token is cancelled first, in which case the error
+/// [`Cancelled`][crate::ManifestError::Cancelled] is returned.
+pub async fn sleep(duration: Duration, token: Option<&CancellationToken>) -> Result<()> {
+    if duration.is_zero() {
+        return Ok(());
+    }
+    let Some(token) = token else {
+        tokio::time::sleep(duration).await;
+        return Ok(());
+    };
+
+    tokio::select! {
+        () = tokio::time::sleep(duration) => Ok(()),
+        () = token.cancelled() => Err(ManifestError::Cancelled),
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Proxy};
+
+use crate::parser::options::DEFAULT_MAX_CHUNK_SIZE;
+use crate::Result;
+
+/// Configuration used to build a [`Client`] for downloading files.
+///
+/// If you need full control over the client, you can instead construct a [`Client`] yourself
+/// and pass it to [`File::download_with_client`][crate::File::download_with_client].
+///
+/// # Examples
+///
+/// ```rust
+/// use std::time::Duration;
+///
+/// # use rman::Result;
+/// use rman::DownloadConfig;
+///
+/// # fn main() -> Result<()> {
+/// let config = DownloadConfig {
+///     timeout: Some(Duration::from_secs(30)),
+///     user_agent: Some("my-patcher/1.0".to_owned()),
+///     ..Default::default()
+/// };
+///
+/// let client = config.build_client()?;
+///   # let _ = client;
+///   # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct DownloadConfig {
+    /// Timeout for the entire request, from connecting until the response body has finished.
+    ///
+    /// Defaults to no timeout.
+    pub timeout: Option<Duration>,
+    /// Timeout for only the connect phase of a request.
+    ///
+    /// Defaults to no timeout.
+    pub connect_timeout: Option<Duration>,
+    /// Value of the `User-Agent` header sent with every request.
+    pub user_agent: Option<String>,
+    /// Headers sent with every request.
+    pub default_headers: HeaderMap,
+    /// Proxies used for every request.
+    ///
+    /// If empty, proxies from the environment (`HTTP_PROXY`, `HTTPS_PROXY`, ...) are used.
+    pub proxies: Vec<Proxy>,
+    /// Maximum number of idle connections kept alive per host.
+    ///
+    /// Defaults to no limit.
+    pub pool_max_idle_per_host: Option<usize>,
+    /// Only use HTTP/2, without attempting to negotiate the version first.
+    pub http2_prior_knowledge: bool,
+    /// How many times a failed chunk request is retried before giving up.
+    ///
+    /// Only used by [`Downloader`][crate::Downloader]. Defaults to 0 (no retries).
+    pub max_retries: u32,
+    /// Delay before the first retry, which is doubled after every retry.
+    ///
+    /// Only used by [`Downloader`][crate::Downloader]. Defaults to 500 ms.
+    pub retry_delay: Option<Duration>,
+    /// Maximum amount of compressed bytes downloaded per second.
+    ///
+    /// Only used by [`Downloader`][crate::Downloader]. Defaults to no limit.
+    pub max_bytes_per_second: Option<u64>,
+    /// Maximum amount of requests sent per second.
+    ///
+    /// Only used by [`Downloader`][crate::Downloader]. Defaults to no limit.
+    pub max_requests_per_second: Option<u64>,
+    /// Maximum allowed compressed and uncompressed size of a chunk, or [`None`] for no limit.
+    ///
+    /// Only used by [`Downloader`][crate::Downloader], the same as
+    /// [`with_max_chunk_size`][crate::Downloader::with_max_chunk_size]. Defaults to 64 MiB.
+    pub max_chunk_size: Option<u32>,
+}
+
+impl Default for DownloadConfig {
+    fn default() -> Self {
+        Self {
+            timeout: None,
+            connect_timeout: None,
+            user_agent: None,
+            default_headers: HeaderMap::new(),
+            proxies: Vec::new(),
+            pool_max_idle_per_host: None,
+            http2_prior_knowledge: false,
+            max_retries: 0,
+            retry_delay: None,
+            max_bytes_per_second: None,
+            max_requests_per_second: None,
+            max_chunk_size: Some(DEFAULT_MAX_CHUNK_SIZE),
+        }
+    }
+}
+
+impl DownloadConfig {
+    /// Builds a [`Client`] from the provided configuration.
+    ///
+    /// The resulting client should be reused for as many downloads as possible, so that the
+    /// connections to the CDN can be reused.
+    ///
+    /// # Errors
+    ///
+    /// If building the client fails (e.g. the TLS backend cannot be initialized), the error
+    /// [`ReqwestError`][crate::ManifestError::ReqwestError] is returned.
+    pub fn build_client(&self) -> Result<Client> {
+        let mut builder = Client::builder().default_headers(self.default_headers.clone());
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(connect_timeout);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        for proxy in &self.proxies {
+            builder = builder.proxy(proxy.clone());
+        }
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+
+        Ok(builder.build()?)
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use super::cancel::{self, CancellationToken};
+use crate::Result;
+
+/// Token bucket limiter for downloads.
+///
+/// Limits the amount of compressed bytes downloaded per second, as well as the amount of
+/// requests sent per second. Both limits can be changed at runtime, and are shared between all
+/// clones of the limiter, so the same limiter can be used by multiple
+/// [`Downloader`s][crate::Downloader] that download files concurrently.
+///
+/// Each bucket holds at most one second worth of tokens, which allows short bursts. A request
+/// for more tokens than are currently available is allowed to go through after waiting for the
+/// missing tokens to be refilled.
+///
+/// # Examples
+///
+/// ```rust
+/// use rman::{Downloader, RateLimiter};
+///
+/// // 5 MB/s and 20 requests/s
+/// let limiter = RateLimiter::new(Some(5_000_000), Some(20));
+/// let downloader = Downloader::new(reqwest::Client::new()).with_rate_limiter(limiter.clone());
+///
+/// // later, on a different task
+/// limiter.set_bytes_per_second(Some(1_000_000));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bytes: Arc<Mutex<Bucket>>,
+    requests: Arc<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    /// Creates a new limiter.
+    ///
+    /// If a limit is [`None`], it is not enforced. Limits are clamped to at least 1.
+    #[must_use]
+    pub fn new(bytes_per_second: Option<u64>, requests_per_second: Option<u64>) -> Self {
+        Self {
+            bytes: Arc::new(Mutex::new(Bucket::new(bytes_per_second))),
+            requests: Arc::new(Mutex::new(Bucket::new(requests_per_second))),
+        }
+    }
+
+    /// Changes the maximum amount of bytes downloaded per second.
+    ///
+    /// If the limit is [`None`], it is not enforced. The limit is clamped to at least 1.
+    pub fn set_bytes_per_second(&self, bytes_per_second: Option<u64>) {
+        lock(&self.bytes).set_rate(bytes_per_second);
+    }
+
+    /// Changes the maximum amount of requests sent per second.
+    ///
+    /// If the limit is [`None`], it is not enforced. The limit is clamped to at least 1.
+    pub fn set_requests_per_second(&self, requests_per_second: Option<u64>) {
+        lock(&self.requests).set_rate(requests_per_second);
+    }
+
+    /// Returns the maximum amount of bytes downloaded per second.
+    #[must_use]
+    pub fn bytes_per_second(&self) -> Option<u64> {
+        lock(&self.bytes).rate
+    }
+
+    /// Returns the maximum amount of requests sent per second.
+    #[must_use]
+    pub fn requests_per_second(&self) -> Option<u64> {
+        lock(&self.requests).rate
+    }
+
+    /// Takes the tokens for a request of `bytes`, and waits until they would have been
+    /// available.
+    ///
+    /// The tokens are reserved up front and the locks are released before waiting, so
+    /// concurrent callers only wait for their own share. If the token is cancelled while
+    /// waiting, the error [`Cancelled`][crate::ManifestError::Cancelled] is returned.
+    pub(crate) async fn acquire(
+        &self,
+        bytes: u64,
+        cancellation: Option<&CancellationToken>,
+    ) -> Result<()> {
+        let now = Instant::now();
+        let requests_wait = lock(&self.requests).reserve(1, now);
+        let bytes_wait = lock(&self.bytes).reserve(bytes, now);
+
+        cancel::sleep(requests_wait.max(bytes_wait), cancellation).await
+    }
+}
+
+fn lock(bucket: &Mutex<Bucket>) -> std::sync::MutexGuard<'_, Bucket> {
+    bucket.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[derive(Debug)]
+struct Bucket {
+    rate: Option<u64>,
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl Bucket {
+    fn new(rate: Option<u64>) -> Self {
+        let rate = rate.map(|r| r.max(1));
+        Self {
+            rate,
+            #[allow(clippy::cast_precision_loss)]
+            tokens: rate.unwrap_or_default() as f64,
+            updated_at: Instant::now(),
+        }
+    }
+
+    fn set_rate(&mut self, rate: Option<u64>) {
+        self.refill(Instant::now());
+        self.rate = rate.map(|r| r.max(1));
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated_at).as_secs_f64();
+        self.updated_at = now;
+
+        if let Some(rate) = self.rate {
+            self.tokens = elapsed.mul_add(rate as f64, self.tokens).min(rate as f64);
+        }
+    }
+
+    /// Takes `amount` tokens from the bucket, and returns how long the caller has to wait until
+    /// the tokens would have been available.
+    #[allow(clippy::cast_precision_loss)]
+    fn reserve(&mut self, amount: u64, now: Instant) -> Duration {
+        self.refill(now);
+
+        let Some(rate) = self.rate else {
+            return Duration::ZERO;
+        };
+
+        self.tokens -= amount as f64;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate as f64)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_not_wait_when_unlimited() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(None);
+
+        assert_eq!(bucket.reserve(u64::MAX, now), Duration::ZERO);
+    }
+
+    #[test]
+    fn should_wait_when_bucket_is_empty() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(Some(100));
+        bucket.updated_at = now;
+
+        assert_eq!(bucket.reserve(100, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(50, now), Duration::from_millis(500));
+        assert_eq!(bucket.reserve(50, now), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn should_refill_over_time() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(Some(100));
+        bucket.updated_at = now;
+
+        assert_eq!(bucket.reserve(100, now), Duration::ZERO);
+        let later = now + Duration::from_millis(500);
+        assert_eq!(bucket.reserve(50, later), Duration::ZERO);
+        assert_eq!(bucket.reserve(50, later), Duration::from_millis(500));
+    }
+
+    #[tokio::test]
+    async fn should_stop_waiting_when_cancelled() {
+        let limiter = RateLimiter::new(Some(1), None);
+        let token = CancellationToken::new();
+
+        let cancel_token = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            cancel_token.cancel();
+        });
+
+        let result = tokio::time::timeout(
+            Duration::from_secs(5),
+            limiter.acquire(1_000_000, Some(&token)),
+        )
+        .await
+        .expect("acquire did not stop waiting");
+        let Err(crate::ManifestError::Cancelled) = result else {
+            panic!("did not throw a cancelled error");
+        };
+    }
+
+    #[test]
+    fn should_not_refill_above_rate() {
+        let now = Instant::now();
+        let mut bucket = Bucket::new(Some(100));
+        bucket.updated_at = now;
+
+        let later = now + Duration::from_secs(10);
+        assert_eq!(bucket.reserve(100, later), Duration::ZERO);
+        assert_eq!(bucket.reserve(100, later), Duration::from_secs(1));
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+
+use crate::{File, ManifestError};
+
+/// Snapshot of how far a download has progressed.
+///
+/// All sizes are in bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    /// Amount of compressed bytes downloaded so far.
+    pub downloaded_bytes: u64,
+    /// Amount of bytes produced by decompressing downloaded chunks.
+    pub decompressed_bytes: u64,
+    /// Amount of bytes written to the output.
+    pub written_bytes: u64,
+    /// Total amount of bytes that will be written once the download completes.
+    ///
+    /// This is the sum of [uncompressed sizes](crate::entries::ChunkEntry::uncompressed_size) of
+    /// all chunks.
+    pub total_bytes: u64,
+    /// Amount of chunks that were written to the output.
+    pub completed_chunks: u64,
+    /// Total amount of chunks that will be written once the download completes.
+    pub total_chunks: u64,
+    /// Amount of files that finished downloading.
+    pub completed_files: u64,
+    /// Total amount of files that will be downloaded.
+    pub total_files: u64,
+}
+
+impl Progress {
+    pub(crate) fn add_file(&mut self, file: &File) {
+        self.total_files += 1;
+        self.total_chunks += file.chunks.len() as u64;
+        self.total_bytes += file
+            .chunks
+            .iter()
+            .map(|c| u64::from(c.uncompressed_size))
+            .sum::<u64>();
+    }
+}
+
+/// Kind of the [`ProgressEvent`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ProgressEventKind<'a> {
+    /// The file started downloading.
+    FileStarted,
+    /// A chunk was downloaded.
+    ChunkDownloaded {
+        /// Index of the chunk in [`File::chunks`][crate::File::chunks].
+        chunk_index: usize,
+        /// Amount of compressed bytes that were downloaded.
+        bytes: u64,
+    },
+    /// A chunk was found in the [`ChunkCache`][crate::ChunkCache], so it won't be downloaded.
+    ChunkCached {
+        /// Index of the chunk in [`File::chunks`][crate::File::chunks].
+        chunk_index: usize,
+    },
+    /// A chunk was decompressed.
+    ChunkDecompressed {
+        /// Index of the chunk in [`File::chunks`][crate::File::chunks].
+        chunk_index: usize,
+        /// Amount of bytes produced by decompression.
+        bytes: u64,
+    },
+    /// A chunk was written to the output.
+    ChunkCompleted {
+        /// Index of the chunk in [`File::chunks`][crate::File::chunks].
+        chunk_index: usize,
+        /// Amount of bytes that were written.
+        bytes: u64,
+    },
+    /// Request for a chunk failed, and will be retried.
+    Retry {
+        /// Index of the chunk in [`File::chunks`][crate::File::chunks].
+        chunk_index: usize,
+        /// Which retry this is, starting at 1.
+        attempt: u32,
+        /// Error that caused the retry.
+        error: &'a ManifestError,
+    },
+    /// The file finished downloading.
+    FileCompleted,
+    /// Downloading the file failed.
+    ///
+    /// The same error is returned from the download function after this event.
+    Error {
+        /// Error that caused the download to fail.
+        error: &'a ManifestError,
+    },
+}
+
+/// Event reported to a [`ProgressSink`].
+#[derive(Debug)]
+pub struct ProgressEvent<'a> {
+    /// File that this event belongs to.
+    pub file: &'a File,
+    /// What happened.
+    pub kind: ProgressEventKind<'a>,
+    /// Progress of the [`file`](ProgressEvent::file).
+    pub file_progress: Progress,
+    /// Progress of all of the files that are being downloaded.
+    ///
+    /// When downloading a single file, this is the same as
+    /// [`file_progress`](ProgressEvent::file_progress), except for the file counts.
+    pub overall_progress: Progress,
+}
+
+/// Receiver of download [progress events][ProgressEvent].
+///
+/// It is implemented for all closures that take a [`ProgressEvent`], so a closure can be passed
+/// directly to [`Downloader::with_progress`][crate::Downloader::with_progress].
+///
+/// Events are reported synchronously from the download task, so implementations should return
+/// quickly (e.g. by updating a progress bar or sending the data over a channel).
+pub trait ProgressSink: Send + Sync {
+    /// Called for every event that occurs during a download.
+    fn on_event(&self, event: &ProgressEvent<'_>);
+}
+
+impl<F: Fn(&ProgressEvent<'_>) + Send + Sync> ProgressSink for F {
+    fn on_event(&self, event: &ProgressEvent<'_>) {
+        self(event);
+    }
+}
+
+impl fmt::Debug for dyn ProgressSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressSink")
+    }
+}
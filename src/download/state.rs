@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+
+use crate::{File, FileId, ManifestError, Result};
+
+/// Persisted state of a resumable download.
+///
+/// Records how many chunks of each [file](crate::File) were written to disk for a given
+/// [manifest id](crate::Header::manifest_id). Chunks of a file are always written in order, so
+/// the state only needs to store the amount of written chunks per file.
+///
+/// The state is stored in a small text file, where the first line contains the manifest id, and
+/// each following line contains a file id and the amount of written chunks, separated by a
+/// space. Ids are stored as 16 digit hex values.
+///
+/// # Examples
+///
+/// See [resuming a download](index.html#example-resuming-a-download).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DownloadState {
+    path: PathBuf,
+    manifest_id: u64,
+    completed_chunks: HashMap<FileId, usize>,
+}
+
+impl DownloadState {
+    /// Opens the state file at `path`.
+    ///
+    /// If the file does not exist, or it belongs to a different manifest id, an empty state is
+    /// returned instead. Nothing is written to disk until [`save`](DownloadState::save) is
+    /// called.
+    ///
+    /// # Errors
+    ///
+    /// If reading the file fails, the error [`IoError`][crate::ManifestError::IoError] is
+    /// returned.
+    ///
+    /// If the file is malformed, the error
+    /// [`DownloadStateError`][crate::ManifestError::DownloadStateError] is returned.
+    pub fn open<P: AsRef<Path>>(path: P, manifest_id: u64) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut state = Self {
+            path,
+            manifest_id,
+            completed_chunks: HashMap::new(),
+        };
+
+        let file = match fs::File::open(&state.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+                debug!("State file does not exist, starting from scratch.");
+                return Ok(state);
+            }
+            Err(error) => return Err(ManifestError::IoError(error)),
+        };
+
+        let mut lines = BufReader::new(file).lines();
+
+        let Some(first_line) = lines.next() else {
+            return Ok(state);
+        };
+        let Ok(stored_manifest_id) = u64::from_str_radix(first_line?.trim(), 16) else {
+            let message = "could not parse manifest id".to_owned();
+            return Err(ManifestError::DownloadStateError(message));
+        };
+
+        if stored_manifest_id != manifest_id {
+            info!("State file belongs to a different manifest, starting from scratch.");
+            return Ok(state);
+        }
+
+        for line in lines {
+            let line = line?;
+            let Some((file_id, completed)) = line.split_once(' ') else {
+                let message = format!("invalid line: \"{line}\"");
+                return Err(ManifestError::DownloadStateError(message));
+            };
+            let (Ok(file_id), Ok(completed)) = (file_id.parse::<FileId>(), completed.parse())
+            else {
+                let message = format!("invalid line: \"{line}\"");
+                return Err(ManifestError::DownloadStateError(message));
+            };
+            state.completed_chunks.insert(file_id, completed);
+        }
+
+        Ok(state)
+    }
+
+    /// Writes the state to disk.
+    ///
+    /// The state is first written to a temporary file, which is then renamed, so that an
+    /// interrupted write never leaves a corrupted state file behind.
+    ///
+    /// # Errors
+    ///
+    /// If writing the file fails, the error [`IoError`][crate::ManifestError::IoError] is
+    /// returned.
+    pub fn save(&self) -> Result<()> {
+        let mut tmp_path = self.path.clone().into_os_string();
+        tmp_path.push(".tmp");
+
+        let mut writer = BufWriter::new(fs::File::create(&tmp_path)?);
+        writeln!(writer, "{:016X}", self.manifest_id)?;
+        for (file_id, completed) in &self.completed_chunks {
+            writeln!(writer, "{file_id} {completed}")?;
+        }
+        writer
+            .into_inner()
+            .map_err(std::io::IntoInnerError::into_error)?
+            .sync_all()?;
+
+        fs::rename(tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    /// Removes the state file from disk, if it exists.
+    ///
+    /// # Errors
+    ///
+    /// If removing the file fails, the error [`IoError`][crate::ManifestError::IoError] is
+    /// returned.
+    pub fn remove(self) -> Result<()> {
+        match fs::remove_file(&self.path) {
+            Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error.into()),
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns the path of the state file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the manifest id the state belongs to.
+    #[must_use]
+    pub const fn manifest_id(&self) -> u64 {
+        self.manifest_id
+    }
+
+    /// Returns the amount of chunks of the file that were written to disk.
+    #[must_use]
+    pub fn completed_chunks(&self, file: &File) -> usize {
+        self.completed_chunks
+            .get(&file.id)
+            .copied()
+            .unwrap_or_default()
+            .min(file.chunks.len())
+    }
+
+    /// Returns `true` if all of the chunks of the file were written to disk.
+    #[must_use]
+    pub fn is_completed(&self, file: &File) -> bool {
+        self.completed_chunks.contains_key(&file.id)
+            && self.completed_chunks(file) == file.chunks.len()
+    }
+
+    pub(crate) fn set_completed_chunks(&mut self, file: &File, completed: usize) {
+        self.completed_chunks.insert(file.id, completed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+
+    use crate::FileChunk;
+
+    fn file(id: i64, chunks: usize) -> File {
+        File {
+            id: id.into(),
+            chunks: vec![FileChunk::default(); chunks],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_restore_completed_chunks_when_saved() {
+        let path = env::temp_dir().join("rman-should-restore-completed-chunks.state");
+        let mut state = DownloadState::open(&path, 1).unwrap();
+        state.set_completed_chunks(&file(1, 3), 2);
+        state.set_completed_chunks(&file(2, 1), 1);
+        state.save().unwrap();
+
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.starts_with("0000000000000001\n"));
+        assert!(contents.contains("\n0000000000000001 2\n"));
+
+        let state = DownloadState::open(&path, 1).unwrap();
+        assert_eq!(state.completed_chunks(&file(1, 3)), 2);
+        assert!(!state.is_completed(&file(1, 3)));
+        assert!(state.is_completed(&file(2, 1)));
+        assert_eq!(state.completed_chunks(&file(3, 1)), 0);
+
+        state.remove().unwrap();
+    }
+
+    #[test]
+    fn should_start_from_scratch_when_manifest_id_differs() {
+        let path = env::temp_dir().join("rman-should-start-from-scratch.state");
+        let mut state = DownloadState::open(&path, 1).unwrap();
+        state.set_completed_chunks(&file(1, 1), 1);
+        state.save().unwrap();
+
+        let state = DownloadState::open(&path, 2).unwrap();
+        assert_eq!(state.completed_chunks(&file(1, 1)), 0);
+
+        state.remove().unwrap();
+    }
+
+    #[test]
+    fn should_error_when_malformed() {
+        let path = env::temp_dir().join("rman-should-error-when-malformed.state");
+        fs::write(&path, "0000000000000001\nnot a valid line\n").unwrap();
+
+        let Err(error) = DownloadState::open(&path, 1) else {
+            panic!("did not throw an error");
+        };
+        let ManifestError::DownloadStateError(..) = error else {
+            panic!("some other error was thrown");
+        };
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -14,7 +14,7 @@ mod tag_entry;
 
 pub use self::bundle_entry::BundleEntry;
 pub use self::chunk_entry::ChunkEntry;
-pub use self::chunking_param_entry::ChunkingParamEntry;
+pub use self::chunking_param_entry::{ChunkHashType, ChunkingParamEntry};
 pub use self::directory_entry::DirectoryEntry;
 pub use self::file_entry::FileEntry;
 pub use self::key_entry::KeyEntry;
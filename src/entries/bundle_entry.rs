@@ -1,4 +1,5 @@
 use crate::generated::rman::Bundle;
+use crate::BundleId;
 
 use super::chunk_entry::ChunkEntry;
 
@@ -12,14 +13,14 @@ use super::chunk_entry::ChunkEntry;
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct BundleEntry {
     /// Id of the bundle entry.
-    pub id: i64,
+    pub id: BundleId,
     /// A vector of chunk entries.
     pub chunks: Vec<ChunkEntry>,
 }
 
 impl From<Bundle<'_>> for BundleEntry {
     fn from(bundle: Bundle) -> Self {
-        let id = bundle.id();
+        let id = bundle.id().into();
         let chunks = bundle.chunks().unwrap_or_default();
 
         let chunks = chunks.iter().map(ChunkEntry::from).collect();
@@ -1,4 +1,5 @@
 use crate::generated::rman::Chunk;
+use crate::ChunkId;
 
 /// Single chunk entry object.
 ///
@@ -10,7 +11,7 @@ use crate::generated::rman::Chunk;
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
 pub struct ChunkEntry {
     /// Id of the chunk entry.
-    pub id: i64,
+    pub id: ChunkId,
     /// Chunk size before decompression.
     ///
     /// Mainly used when downloading files.
@@ -23,7 +24,7 @@ pub struct ChunkEntry {
 
 impl From<Chunk<'_>> for ChunkEntry {
     fn from(chunk: Chunk) -> Self {
-        let id = chunk.id();
+        let id = chunk.id().into();
         let compressed_size = chunk.compressed_size();
         let uncompressed_size = chunk.uncompressed_size();
 
@@ -16,7 +16,9 @@ pub struct ChunkingParamEntry {
     /// - 0 - Invalid/None
     /// - 1 - SHA256
     /// - 2 - SHA512
-    /// - 3 - RIOT_HKDF
+    /// - 3 - `RIOT_HKDF`
+    ///
+    /// Use [`hash_type`](ChunkingParamEntry::hash_type) to get it as a [`ChunkHashType`].
     ///
     /// These values are copied straight from
     /// [moonshadow565's implementation][moonshadow565-rman-rchunk].
@@ -48,6 +50,60 @@ pub struct ChunkingParamEntry {
     pub max_chunk_size: u32,
 }
 
+/// Hash type used when generating chunks.
+///
+/// Parsed from [`ChunkingParamEntry::chunking_version`]. The values are copied straight from
+/// [moonshadow565's implementation][moonshadow565-rman-rchunk].
+///
+/// [moonshadow565-rman-rchunk]: https://github.com/moonshadow565/rman/blob/master/lib/rlib/rchunk.hpp
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChunkHashType {
+    /// Invalid, or no hash (`0`).
+    None,
+    /// SHA-256 (`1`).
+    Sha256,
+    /// SHA-512 (`2`).
+    Sha512,
+    /// HKDF, as implemented by Riot (`3`).
+    RiotHkdf,
+    /// Any other, unknown value.
+    Unknown(u8),
+}
+
+impl From<u8> for ChunkHashType {
+    fn from(value: u8) -> Self {
+        match value {
+            0 => Self::None,
+            1 => Self::Sha256,
+            2 => Self::Sha512,
+            3 => Self::RiotHkdf,
+            value => Self::Unknown(value),
+        }
+    }
+}
+
+impl From<ChunkHashType> for u8 {
+    fn from(hash_type: ChunkHashType) -> Self {
+        match hash_type {
+            ChunkHashType::None => 0,
+            ChunkHashType::Sha256 => 1,
+            ChunkHashType::Sha512 => 2,
+            ChunkHashType::RiotHkdf => 3,
+            ChunkHashType::Unknown(value) => value,
+        }
+    }
+}
+
+impl ChunkingParamEntry {
+    /// Returns the [`chunking_version`](ChunkingParamEntry::chunking_version) as a
+    /// [`ChunkHashType`].
+    #[must_use]
+    pub fn hash_type(&self) -> ChunkHashType {
+        self.chunking_version.into()
+    }
+}
+
 impl From<ChunkingParam<'_>> for ChunkingParamEntry {
     fn from(chunking_param: ChunkingParam) -> Self {
         let unk0 = chunking_param.unk0();
@@ -1,4 +1,5 @@
 use crate::generated::rman::Directory;
+use crate::DirectoryId;
 
 /// Single directory entry object.
 ///
@@ -10,20 +11,20 @@ use crate::generated::rman::Directory;
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct DirectoryEntry {
     /// Id of the directory entry.
-    pub id: i64,
+    pub id: DirectoryId,
     /// Id of the parent directory entry.
     ///
-    /// NOTE: root directory (which is tipically the first DirectoryEntry in the vector) typically
+    /// NOTE: root directory (which is tipically the first `DirectoryEntry` in the vector) typically
     /// has an `id` of 0, yet still has a `parent_id` of 0.
-    pub parent_id: i64,
+    pub parent_id: DirectoryId,
     /// Name of the directory entry.
     pub name: String,
 }
 
 impl From<Directory<'_>> for DirectoryEntry {
     fn from(directory: Directory) -> Self {
-        let id = directory.id();
-        let parent_id = directory.parent_id();
+        let id = directory.id().into();
+        let parent_id = directory.parent_id().into();
         let name = directory.name().unwrap_or_default().to_owned();
 
         Self {
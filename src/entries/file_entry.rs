@@ -1,4 +1,6 @@
+use super::ChunkingParamEntry;
 use crate::generated::rman::File;
+use crate::{ChunkId, DirectoryId, FileId, FilePermissions};
 
 /// Single file entry object.
 ///
@@ -10,9 +12,9 @@ use crate::generated::rman::File;
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct FileEntry {
     /// Id of the file entry.
-    pub id: i64,
+    pub id: FileId,
     /// Id of the directory entry, to which it belongs.
-    pub directory_id: i64,
+    pub directory_id: DirectoryId,
     /// Size of the file entry in bytes.
     pub size: u32,
     /// Name of the file entry.
@@ -24,7 +26,7 @@ pub struct FileEntry {
     /// Field with an unknown function and type (it might also be an [`i8`]).
     pub unk6: u8,
     /// A vector of [chunk ids](crate::entries::ChunkEntry::id) that make up the file.
-    pub chunk_ids: Vec<i64>,
+    pub chunk_ids: Vec<ChunkId>,
     /// Field with an unknown function and type (it might also be an [`i8`]).
     ///
     /// NOTE: seems to always be 1 when a part of `.app` file on macOS.
@@ -34,15 +36,33 @@ pub struct FileEntry {
     /// Field with an unknown function and type (it might also be an [`i16`]).
     pub unk10: u16,
     /// Id of the chunking param entry, which provides info about content-defined chunking.
+    ///
+    /// It is an index into [`chunking_param_entries`][crate::ManifestData::chunking_param_entries],
+    /// see [`chunking_param`](FileEntry::chunking_param).
     pub chunking_param_id: u8,
     /// Permissions for the given file entry.
-    pub permissions: u8,
+    pub permissions: FilePermissions,
+}
+
+impl FileEntry {
+    /// Returns the [chunking param entry][crate::entries::ChunkingParamEntry] of this file, or
+    /// [`None`] if [`chunking_param_id`](FileEntry::chunking_param_id) is out of bounds.
+    ///
+    /// The parameter `chunking_param_entries` should be
+    /// [`ManifestData::chunking_param_entries`][crate::ManifestData::chunking_param_entries].
+    #[must_use]
+    pub fn chunking_param<'a>(
+        &self,
+        chunking_param_entries: &'a [ChunkingParamEntry],
+    ) -> Option<&'a ChunkingParamEntry> {
+        chunking_param_entries.get(usize::from(self.chunking_param_id))
+    }
 }
 
 impl From<File<'_>> for FileEntry {
     fn from(file: File) -> Self {
-        let id = file.id();
-        let directory_id = file.directory_id();
+        let id = file.id().into();
+        let directory_id = file.directory_id().into();
         let size = file.size_();
         let name = file.name().unwrap_or_default().to_owned();
         let tag_bitmask = file.tag_bitmask();
@@ -53,9 +73,9 @@ impl From<File<'_>> for FileEntry {
         let symlink = file.symlink().unwrap_or_default().to_owned();
         let unk10 = file.unk10();
         let chunking_param_id = file.chunking_param_id();
-        let permissions = file.permissions();
+        let permissions = FilePermissions::from_bits_retain(file.permissions());
 
-        let chunk_ids = chunk_ids.iter().collect();
+        let chunk_ids = chunk_ids.iter().map(ChunkId::from).collect();
 
         Self {
             id,
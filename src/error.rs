@@ -26,9 +26,9 @@ pub enum ManifestError {
     /// Should only occur if the manifest format gets a major change or an update, Parser
     /// may no longer function if this happens.
     ///
-    /// NOTE: The feature `version_error` must be enabled for this error to occur.
+    /// NOTE: This error only occurs if the [`VersionPolicy`][crate::VersionPolicy] rejects the
+    /// version.
     #[error("unsupported major version (expected: \"2\", was: \"{0}\")")]
-    #[cfg(any(feature = "version_error", doc))]
     InvalidMajor(u8),
     /// The error was caused by invalid minor version.
     ///
@@ -37,9 +37,9 @@ pub enum ManifestError {
     /// Should only occur if the manifest format gets a minor change or an update. Parser
     /// should still be functional if this happens,
     ///
-    /// NOTE: The feature `version_error` must be enabled for this error to occur.
+    /// NOTE: This error only occurs if the [`VersionPolicy`][crate::VersionPolicy] rejects the
+    /// version.
     #[error("unsupported minor version (expected: \"0\", was: \"{0}\")")]
-    #[cfg(any(feature = "version_error", doc))]
     InvalidMinor(u8),
     /// The error was caused by an invalid offset.
     ///
@@ -55,6 +55,86 @@ pub enum ManifestError {
     /// Should never happen for official, Riot-made manifests.
     #[error("compressed size ({0}) is larger than the total file size")]
     CompressedSizeTooLarge(u32),
+    /// The error was caused by compressed size exceeding the configured limit.
+    ///
+    /// This error occurs when the [compressed size](crate::Header::compressed_size) is larger
+    /// than the limit set with
+    /// [`ParseOptions::with_max_compressed_size`][crate::ParseOptions::with_max_compressed_size].
+    #[error("compressed size ({0}) exceeds the configured limit")]
+    CompressedSizeLimitExceeded(u32),
+    /// The error was caused by uncompressed size exceeding the configured limit.
+    ///
+    /// This error occurs when the [uncompressed size](crate::Header::uncompressed_size) is
+    /// larger than the limit set with
+    /// [`ParseOptions::with_max_uncompressed_size`][crate::ParseOptions::with_max_uncompressed_size].
+    #[error("uncompressed size ({0}) exceeds the configured limit")]
+    UncompressedSizeLimitExceeded(u32),
+    /// The error was caused by the amount of files exceeding the configured limit.
+    ///
+    /// This error occurs when the manifest contains more [file entries][crate::entries::FileEntry]
+    /// than the limit set with
+    /// [`ParseOptions::with_max_files`][crate::ParseOptions::with_max_files].
+    #[error("amount of files ({0}) exceeds the configured limit")]
+    FileCountLimitExceeded(usize),
+    /// The error was caused by the amount of directories exceeding the configured limit.
+    ///
+    /// This error occurs when the manifest contains more
+    /// [directory entries][crate::entries::DirectoryEntry] than the limit set with
+    /// [`ParseOptions::with_max_directories`][crate::ParseOptions::with_max_directories].
+    #[error("amount of directories ({0}) exceeds the configured limit")]
+    DirectoryCountLimitExceeded(usize),
+    /// The error was caused by a file path exceeding the configured length limit.
+    ///
+    /// This error occurs when the [path](crate::File::path) of a resolved file is longer (in
+    /// bytes) than the limit set with
+    /// [`ParseOptions::with_max_path_length`][crate::ParseOptions::with_max_path_length].
+    #[error("path length ({0}) exceeds the configured limit")]
+    PathLengthLimitExceeded(usize),
+    /// The error was caused by a file being nested too deep.
+    ///
+    /// This error occurs when a file is nested in more directories than the limit set with
+    /// [`ParseOptions::with_max_directory_depth`][crate::ParseOptions::with_max_directory_depth].
+    #[error("directory depth ({0}) exceeds the configured limit")]
+    DirectoryDepthLimitExceeded(usize),
+    /// The error was caused by directories forming a cycle.
+    ///
+    /// This error occurs when following the [parent ids](crate::entries::DirectoryEntry::parent_id)
+    /// of a file's directory never reaches the root. Contains the id of a directory that is part
+    /// of the cycle.
+    #[error("directory with the id \"{0}\" is part of a cycle")]
+    DirectoryCycle(crate::DirectoryId),
+    /// The error was caused by a file path that is not safe to write to disk.
+    ///
+    /// This error occurs when validating a [`ManifestPath`][crate::ManifestPath], if the path
+    /// is empty, absolute, could escape the install root, or contains a component that is not
+    /// allowed by the [`PathStrictness`][crate::PathStrictness].
+    #[error("unsafe file path {0}")]
+    UnsafePath(String),
+    /// The error was caused by a chunk size exceeding the configured limit.
+    ///
+    /// This error occurs when either the compressed or the uncompressed size of a
+    /// [chunk][crate::entries::ChunkEntry] is larger than the limit set with
+    /// [`ParseOptions::with_max_chunk_size`][crate::ParseOptions::with_max_chunk_size] or
+    /// [`Downloader::with_max_chunk_size`][crate::Downloader::with_max_chunk_size].
+    #[error("chunk size ({0}) exceeds the configured limit")]
+    ChunkSizeLimitExceeded(u32),
+    /// The error was caused by a chunk whose byte range within its bundle is invalid.
+    ///
+    /// This error occurs when the offset of a chunk in its bundle (the sum of the
+    /// [compressed sizes](crate::entries::ChunkEntry::compressed_size) of the chunks before it)
+    /// plus its own compressed size does not fit into a [`u32`], or when a chunk with a
+    /// compressed size of 0 is downloaded.
+    ///
+    /// Should never happen for official, Riot-made manifests.
+    #[error("chunk with the id \"{0}\" has an invalid byte range")]
+    InvalidChunkRange(crate::ChunkId),
+    /// The error was caused by an invalid id string.
+    ///
+    /// This error occurs when [parsing][std::str::FromStr] a [`BundleId`][crate::BundleId],
+    /// [`ChunkId`][crate::ChunkId], [`FileId`][crate::FileId] or
+    /// [`DirectoryId`][crate::DirectoryId] from a string that is not made of 1 to 16 hex digits.
+    #[error("{0}")]
+    InvalidId(String),
     /// The error was caused by a failure to read or write bytes on an IO stream.
     ///
     /// This error occurs when [`read_exact`][std::io::Read::read_exact], any `read_` method in
@@ -72,11 +152,30 @@ pub enum ManifestError {
     ConversionFailure(#[from] std::num::TryFromIntError),
     /// The error was caused by a failure to decompress zstd data.
     ///
-    /// This error occurs when [`decompress`][zstd::bulk::decompress] fails.
+    /// This error occurs when [`decompress`][zstd::bulk::decompress] or reading from a
+    /// [`Decoder`][zstd::Decoder] fails.
     ///
     /// Should never happen for official, Riot-made manifests.
     #[error("{0}")]
     ZstdDecompressError(std::io::Error),
+    /// The error was caused by a failure to compress zstd data.
+    ///
+    /// This error occurs when [`compress`][zstd::bulk::compress] fails while
+    /// [writing a manifest][crate::RiotManifest::to_bytes].
+    #[error("{0}")]
+    ZstdCompressError(std::io::Error),
+    /// The error was caused by decompressed data not matching the expected size.
+    ///
+    /// This error occurs when the size of the decompressed flatbuffer binary does not equal the
+    /// [uncompressed size](crate::Header::uncompressed_size) from the header, or when the size
+    /// of a downloaded chunk does not equal its
+    /// [uncompressed size](crate::FileChunk::uncompressed_size). The first value is the expected
+    /// size, and the second one is the actual size (which is at most one byte larger than
+    /// expected, since decompression stops there).
+    ///
+    /// Should never happen for official, Riot-made manifests.
+    #[error("decompressed size ({1}) does not match the uncompressed size ({0})")]
+    UncompressedSizeMismatch(u32, usize),
     /// The error was caused by a failure to parse [`FileEntry`][crate::entries::FileEntry] into
     /// [`File`][crate::File].
     ///
@@ -104,4 +203,15 @@ pub enum ManifestError {
     /// provided.
     #[error("{0}")]
     ReqwestError(#[from] reqwest::Error),
+    /// The error was caused by a cancelled download.
+    ///
+    /// This error occurs when a [`CancellationToken`][crate::CancellationToken] used by the
+    /// [`Downloader`][crate::Downloader] is cancelled.
+    #[error("download was cancelled")]
+    Cancelled,
+    /// The error was caused by a failure to parse a [`DownloadState`][crate::DownloadState] file.
+    ///
+    /// This error occurs when the state file exists, but its contents are malformed.
+    #[error("invalid download state, error: {0}")]
+    DownloadStateError(String),
 }
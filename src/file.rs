@@ -1,13 +1,16 @@
 use std::collections::HashMap;
 use std::io::Write;
+use std::sync::Arc;
 
-use log::debug;
-use reqwest::header;
+use bitflags::bitflags;
 use reqwest::Client;
 use reqwest::IntoUrl;
 
 use crate::entries::FileEntry;
-use crate::{ManifestError, Result};
+use crate::{
+    BundleId, ChunkId, DirectoryId, Downloader, FileId, ManifestError, ManifestPath, ParseOptions,
+    PathStrictness, Result,
+};
 
 /// Single file object.
 ///
@@ -16,11 +19,11 @@ use crate::{ManifestError, Result};
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct File {
     /// Id of the file.
-    pub id: i64,
+    pub id: FileId,
     /// File name.
     pub name: String,
     /// Permissions for the given file.
-    pub permissions: u8,
+    pub permissions: FilePermissions,
     /// Size of the file entry in bytes.
     pub size: u32,
     /// Absolute path to the file, where root is one of the
@@ -30,35 +33,126 @@ pub struct File {
     pub symlink: String,
     /// A vector of applicable tags.
     pub tags: Vec<String>,
-    /// Vector of file chunks.
+    /// Vector of file chunks, in the order in which they make up the file.
+    pub chunks: Vec<FileChunk>,
+    /// [Id of the chunking param entry](crate::entries::FileEntry::chunking_param_id) of the
+    /// file.
     ///
-    /// Tuple represents `bundle_id`, `offset`, `uncompressed_size`, and `compressed_size` (in
-    /// that order).
-    pub chunks: Vec<(i64, u32, u32, u32)>,
+    /// Use [`ManifestData::chunking_param`][crate::ManifestData::chunking_param] to get the
+    /// entry itself.
+    pub chunking_param_id: u8,
+}
+
+bitflags! {
+    /// [Permissions](File::permissions) of a file.
+    ///
+    /// Only the executable bit is known, the rest of the bits are preserved as they are and can
+    /// be accessed with [`bits`](FilePermissions::bits).
+    ///
+    /// Permissions are applied automatically on Unix when writing files with
+    /// [`Downloader::download_files`][crate::Downloader::download_files] (and its resumable
+    /// variant).
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct FilePermissions: u8 {
+        /// The file is executable.
+        ///
+        /// Set on binaries in macOS and Linux manifests.
+        const EXECUTABLE = 1;
+
+        // the other bits are not understood yet, but are kept so that they can still be
+        // inspected and are not lost when the file entry is written back
+        const _ = !0;
+    }
+}
+
+impl FilePermissions {
+    /// Returns whether the [executable](FilePermissions::EXECUTABLE) bit is set.
+    #[must_use]
+    pub const fn is_executable(&self) -> bool {
+        self.contains(Self::EXECUTABLE)
+    }
+
+    /// Converts the permissions to a Unix file mode.
+    ///
+    /// Executable files get the mode `0o755`, and all other files `0o644`.
+    #[must_use]
+    pub const fn unix_mode(&self) -> u32 {
+        if self.is_executable() {
+            0o755
+        } else {
+            0o644
+        }
+    }
+
+    /// Converts the permissions to [`std::fs::Permissions`] with the
+    /// [Unix mode](FilePermissions::unix_mode), that can be passed to
+    /// [`std::fs::set_permissions`].
+    #[cfg(unix)]
+    #[must_use]
+    pub fn to_std(&self) -> std::fs::Permissions {
+        use std::os::unix::fs::PermissionsExt;
+
+        std::fs::Permissions::from_mode(self.unix_mode())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for FilePermissions {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.bits())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for FilePermissions {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        <u8 as serde::Deserialize>::deserialize(deserializer).map(Self::from_bits_retain)
+    }
+}
+
+/// Single chunk of a [`File`].
+///
+/// Combines the [chunk entry][crate::entries::ChunkEntry] with the data about the
+/// [bundle][crate::entries::BundleEntry] it belongs to, which is required to download it.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FileChunk {
+    /// Id of the chunk.
+    pub id: ChunkId,
+    /// Id of the bundle the chunk belongs to.
+    pub bundle_id: BundleId,
+    /// Offset in bundle (to this specific chunk).
+    pub offset: u32,
+    /// Chunk size after decompression.
+    pub uncompressed_size: u32,
+    /// Chunk size before decompression.
+    pub compressed_size: u32,
 }
 
 impl File {
     /// Parses [`FileEntry`] into a [`File`] object.
     ///
-    /// First parameter is a [`FileEntry`] that is parsed into a [`File`], the other three are
-    /// [`HashMap`]s used for fast lookups for the required data.
+    /// First parameter is a [`FileEntry`] that is parsed into a [`File`], the next three are
+    /// [`HashMap`]s used for fast lookups for the required data, and the last one are the
+    /// [`ParseOptions`] used (if [`None`], [`ParseOptions::default()`] is used).
     ///
     /// Here is how they are structured:
     /// - Parameter `tag_entries` is a [`HashMap`] where the key is a
-    /// [tag id](crate::entries::TagEntry::id) and the value is a
-    /// [tag name](crate::entries::TagEntry::name).
+    ///   [tag id](crate::entries::TagEntry::id) and the value is a
+    ///   [tag name](crate::entries::TagEntry::name).
     ///
     /// - Parameter `directories` is a [`HashMap`] where the key is a
-    /// [directory id](crate::entries::DirectoryEntry::id) and the value is a tuple of:
+    ///   [directory id](crate::entries::DirectoryEntry::id) and the value is a tuple of:
     ///   - [directory name](crate::entries::DirectoryEntry::name)
     ///   - and [parent directory id](crate::entries::DirectoryEntry::parent_id).
     ///
     /// - Parameter `chunk_entries` is a [`HashMap`] where the key is a
-    /// [chunk id](crate::entries::ChunkEntry::id) and the value is a tuple of:
-    ///   - [bundle id](crate::entries::BundleEntry::id),
-    ///   - offset in bundle (to this specific chunk),
-    ///   - [uncompressed size](crate::entries::ChunkEntry::uncompressed_size)
-    ///   - and [compressed size](crate::entries::ChunkEntry::compressed_size).
+    ///   [chunk id](crate::entries::ChunkEntry::id) and the value is a [`FileChunk`].
     ///
     /// [`File`]: crate::File
     /// [`FileEntry`]: crate::entries::FileEntry
@@ -71,33 +165,62 @@ impl File {
     /// [chunk id](crate::entries::FileEntry::chunk_ids) does not exist within the `chunk_entries`
     /// [`HashMap`], the error [`FileParseError`][crate::ManifestError::FileParseError] is
     /// returned.
+    ///
+    /// If the directories form a cycle, the error
+    /// [`DirectoryCycle`][crate::ManifestError::DirectoryCycle] is returned.
+    ///
+    /// If the file is nested deeper than the limit set with
+    /// [`ParseOptions::with_max_directory_depth`][crate::ParseOptions::with_max_directory_depth],
+    /// the error
+    /// [`DirectoryDepthLimitExceeded`][crate::ManifestError::DirectoryDepthLimitExceeded] is
+    /// returned.
+    ///
+    /// If the path of the file is longer than the limit set with
+    /// [`ParseOptions::with_max_path_length`][crate::ParseOptions::with_max_path_length], the
+    /// error [`PathLengthLimitExceeded`][crate::ManifestError::PathLengthLimitExceeded] is
+    /// returned.
     pub fn parse(
         file: &FileEntry,
         tag_entries: &HashMap<u8, String>,
-        directories: &HashMap<i64, (String, i64)>,
-        chunk_entries: &HashMap<i64, (i64, u32, u32, u32)>,
+        directories: &HashMap<DirectoryId, (String, DirectoryId)>,
+        chunk_entries: &HashMap<ChunkId, FileChunk>,
+        options: Option<&ParseOptions>,
+    ) -> Result<Self> {
+        let default_options = ParseOptions::default();
+        let options = options.unwrap_or(&default_options);
+
+        let mut directory_paths = DirectoryPaths::new(directories, options);
+        directory_paths.resolve(file.directory_id)?;
+        Self::parse_with_directory_paths(
+            file,
+            tag_entries,
+            &directory_paths,
+            chunk_entries,
+            options,
+        )
+    }
+
+    pub(crate) fn parse_with_directory_paths(
+        file: &FileEntry,
+        tag_entries: &HashMap<u8, String>,
+        directory_paths: &DirectoryPaths<'_>,
+        chunk_entries: &HashMap<ChunkId, FileChunk>,
+        options: &ParseOptions,
     ) -> Result<Self> {
         let id = file.id;
         let name = file.name.clone();
         let permissions = file.permissions;
         let size = file.size;
         let symlink = file.symlink.clone();
+        let chunking_param_id = file.chunking_param_id;
         let tag_bitmask = file.tag_bitmask;
         let chunk_ids = &file.chunk_ids;
 
-        let mut directory_id = file.directory_id;
-        let mut path = String::new();
-
-        while directory_id != 0 {
-            let Some((dir_name, parent_id)) = directories.get(&directory_id) else {
-                let message =
-                    format!("could not find a directory with the following id: \"{directory_id}\"");
-                return Err(ManifestError::FileParseError(message));
-            };
-            path = format!("{dir_name}/{path}");
-            directory_id = *parent_id;
-        }
+        let (directory_path, _) = directory_paths.get(file.directory_id)?;
+        options.check_path_length(directory_path.len() + name.len())?;
 
+        let mut path = String::with_capacity(directory_path.len() + name.len());
+        path.push_str(&directory_path);
         path.push_str(&name);
 
         let mut tags = Vec::new();
@@ -120,7 +243,7 @@ impl File {
                     format!("could not find a chunk with the following id: \"{chunk_id}\"");
                 return Err(ManifestError::FileParseError(message));
             };
-            chunks.push(chunk.to_owned());
+            chunks.push(*chunk);
         }
 
         let file = Self {
@@ -132,26 +255,43 @@ impl File {
             symlink,
             tags,
             chunks,
+            chunking_param_id,
         };
         Ok(file)
     }
 }
 
 impl File {
+    /// Validates the [path](File::path) of the file, returning a [`ManifestPath`] that is safe
+    /// to join to an install root.
+    ///
+    /// # Errors
+    ///
+    /// See [`ManifestPath::new`][crate::ManifestPath::new].
+    pub fn manifest_path(&self, strictness: PathStrictness) -> Result<ManifestPath> {
+        ManifestPath::new(&self.path, strictness)
+    }
+
     /// Function to download the associated file contents.
     ///
     /// This is done by looping through all of the chunks of this file, and for each loop:
-    /// - get the [bundle id](crate::entries::BundleEntry::id) it belongs to, and convert it to
-    /// hexadecimal value with a fixed size of 16 (if the length is less than 16, zeros are
-    /// padded to the left).
+    /// - get the [bundle id](crate::entries::BundleEntry::id) it belongs to, and
+    ///   [display it][crate::BundleId] as a 16 digit hexadecimal value.
     /// - download the chunk from the url using the range header
     /// - [decompress the chunk][zstd::bulk::decompress]
     /// - write chunk.
     ///
     /// # Errors
     ///
-    /// If downloading fails, the error [`ReqwestError`][crate::ManifestError::ReqwestError] is
-    /// returned.
+    /// If downloading fails, or the server responds with an error status code, the error
+    /// [`ReqwestError`][crate::ManifestError::ReqwestError] is returned.
+    ///
+    /// If a chunk is larger than 64 MiB (see
+    /// [`Downloader::with_max_chunk_size`][crate::Downloader::with_max_chunk_size]), the error
+    /// [`ChunkSizeLimitExceeded`][crate::ManifestError::ChunkSizeLimitExceeded] is returned.
+    ///
+    /// If the byte range of a chunk is invalid, the error
+    /// [`InvalidChunkRange`][crate::ManifestError::InvalidChunkRange] is returned.
     ///
     /// If converting [`uncompressed_size`](crate::Header::uncompressed_size) to [`usize`] fails,
     /// the error [`ConversionFailure`][crate::ManifestError::ConversionFailure] is returned.
@@ -159,6 +299,10 @@ impl File {
     /// If zstd decompression fails, the error
     /// [`ZstdDecompressError`][crate::ManifestError::ZstdDecompressError] is returned.
     ///
+    /// If the size of a decompressed chunk does not equal its
+    /// [uncompressed size](crate::FileChunk::uncompressed_size), the error
+    /// [`UncompressedSizeMismatch`][crate::ManifestError::UncompressedSizeMismatch] is returned.
+    ///
     /// If writing to io stream fails, the error [`IoError`][crate::ManifestError::IoError] is
     /// returned.
     ///
@@ -167,34 +311,218 @@ impl File {
     /// See [downloading a file](index.html#example-downloading-a-file).
     pub async fn download<W: Write + Send, U: IntoUrl + Send>(
         &self,
-        mut writer: W,
+        writer: W,
+        bundle_url: U,
+    ) -> Result<()> {
+        self.download_with_client(&Client::new(), writer, bundle_url)
+            .await
+    }
+
+    /// Function to download the associated file contents using the provided [`Client`].
+    ///
+    /// Works the same as [`download`](crate::File::download), except that it uses the provided
+    /// client instead of creating a new one on every call. This allows for connection reuse
+    /// across multiple files, as well as configuring timeouts, proxies, headers, etc. either
+    /// directly on the [`Client`] or through [`DownloadConfig`][crate::DownloadConfig].
+    ///
+    /// For progress reporting and retries, use a [`Downloader`][crate::Downloader] instead.
+    ///
+    /// # Errors
+    ///
+    /// See [`download`](crate::File::download).
+    ///
+    /// # Examples
+    ///
+    /// See
+    /// [downloading multiple files with a shared client](index.html#example-downloading-multiple-files-with-a-shared-client).
+    pub async fn download_with_client<W: Write + Send, U: IntoUrl + Send>(
+        &self,
+        client: &Client,
+        writer: W,
         bundle_url: U,
     ) -> Result<()> {
-        let client = Client::new();
+        Downloader::new(client.clone())
+            .download_file(self, writer, bundle_url)
+            .await
+    }
+}
 
-        for (bundle_id, offset, uncompressed_size, compressed_size) in &self.chunks {
-            let from = offset;
-            let to = offset + compressed_size - 1;
+/// Memoized paths of [directories][crate::entries::DirectoryEntry].
+///
+/// Each directory path is only built once, and then shared between all of the files (and
+/// subdirectories) that are in it. The depth and path length limits from [`ParseOptions`] are
+/// checked while walking up the parents, so a long chain of directories is rejected before any
+/// of its paths are built.
+#[derive(Debug)]
+pub struct DirectoryPaths<'a> {
+    directories: &'a HashMap<DirectoryId, (String, DirectoryId)>,
+    options: &'a ParseOptions,
+    /// Path of the directory, including a trailing `/`, and its depth.
+    resolved: HashMap<DirectoryId, (Arc<str>, usize)>,
+}
 
-            let response = client
-                .get(format!("{}/{bundle_id:016X}.bundle", bundle_url.as_str()))
-                .header(header::RANGE, format!("bytes={from}-{to}"))
-                .send()
-                .await?;
+impl<'a> DirectoryPaths<'a> {
+    pub(crate) fn new(
+        directories: &'a HashMap<DirectoryId, (String, DirectoryId)>,
+        options: &'a ParseOptions,
+    ) -> Self {
+        let mut resolved = HashMap::new();
+        resolved.insert(DirectoryId::ROOT, (Arc::from(""), 0));
 
-            debug!("Attempting to convert \"uncompressed_size\" into \"usize\".");
-            let uncompressed_size: usize = uncompressed_size.to_owned().try_into()?;
-            debug!("Successfully converted \"uncompressed_size\" into \"usize\".");
+        Self {
+            directories,
+            options,
+            resolved,
+        }
+    }
 
-            let decompressed_chunk =
-                match zstd::bulk::decompress(&response.bytes().await?, uncompressed_size) {
-                    Ok(result) => result,
-                    Err(error) => return Err(ManifestError::ZstdDecompressError(error)),
-                };
+    /// Returns the path of the directory with the provided id, including a trailing `/` (or an
+    /// empty path for the root), and the amount of directories it is made of.
+    pub(crate) fn resolve(&mut self, directory_id: DirectoryId) -> Result<(Arc<str>, usize)> {
+        // walk up until a directory that is already resolved is found
+        let mut unresolved = Vec::new();
+        let mut unresolved_length = 0;
+        let mut current_id = directory_id;
+        let (mut path, mut depth) = loop {
+            if let Some((path, depth)) = self.resolved.get(&current_id) {
+                break (Arc::clone(path), *depth);
+            }
+            let Some((name, parent_id)) = self.directories.get(&current_id) else {
+                let message =
+                    format!("could not find a directory with the following id: \"{current_id}\"");
+                return Err(ManifestError::FileParseError(message));
+            };
+            // a path without cycles can't contain more directories than there are in total
+            if unresolved.len() >= self.directories.len() {
+                return Err(ManifestError::DirectoryCycle(current_id));
+            }
+            unresolved.push((current_id, name));
+            unresolved_length += name.len() + 1;
+            // stop as soon as the part walked so far is over the limits
+            self.options.check_directory_depth(unresolved.len())?;
+            self.options.check_path_length(unresolved_length)?;
+            current_id = *parent_id;
+        };
+        self.options
+            .check_directory_depth(depth + unresolved.len())?;
+        self.options
+            .check_path_length(path.len() + unresolved_length)?;
 
-            writer.write_all(&decompressed_chunk)?;
+        // then build the paths back down, memoizing every directory on the way
+        for (id, name) in unresolved.into_iter().rev() {
+            path = Arc::from(format!("{path}{name}/"));
+            depth += 1;
+            self.resolved.insert(id, (Arc::clone(&path), depth));
         }
 
-        Ok(())
+        Ok((path, depth))
+    }
+
+    /// Returns the path of a directory that was already [resolved](DirectoryPaths::resolve).
+    ///
+    /// If the directory could not be resolved, it is resolved again (without memoizing it) to
+    /// return the same error.
+    pub(crate) fn get(&self, directory_id: DirectoryId) -> Result<(Arc<str>, usize)> {
+        if let Some((path, depth)) = self.resolved.get(&directory_id) {
+            return Ok((Arc::clone(path), *depth));
+        }
+        Self::new(self.directories, self.options).resolve(directory_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_share_directory_paths_when_resolved_twice() {
+        let directories = HashMap::from([
+            (DirectoryId(1), ("Game".to_owned(), DirectoryId(0))),
+            (DirectoryId(2), ("DATA".to_owned(), DirectoryId(1))),
+            (DirectoryId(3), ("FINAL".to_owned(), DirectoryId(2))),
+        ]);
+        let options = ParseOptions::default();
+        let mut directory_paths = DirectoryPaths::new(&directories, &options);
+
+        let (path, depth) = directory_paths.resolve(DirectoryId(3)).unwrap();
+        assert_eq!(&*path, "Game/DATA/FINAL/", "path did not match");
+        assert_eq!(depth, 3, "depth did not match");
+
+        let (parent_path, parent_depth) = directory_paths.resolve(DirectoryId(2)).unwrap();
+        assert_eq!(&*parent_path, "Game/DATA/", "path did not match");
+        assert_eq!(parent_depth, 2, "depth did not match");
+
+        let (same_path, _) = directory_paths.resolve(DirectoryId(3)).unwrap();
+        assert!(Arc::ptr_eq(&path, &same_path), "path was not memoized");
+    }
+
+    #[test]
+    fn should_error_when_directory_cycle() {
+        let directories = HashMap::from([
+            (DirectoryId(1), ("a".to_owned(), DirectoryId(2))),
+            (DirectoryId(2), ("b".to_owned(), DirectoryId(1))),
+        ]);
+        let options = ParseOptions::default();
+        let mut directory_paths = DirectoryPaths::new(&directories, &options);
+
+        let Err(error) = directory_paths.resolve(DirectoryId(1)) else {
+            panic!("did not throw an error");
+        };
+        let ManifestError::DirectoryCycle(..) = error else {
+            panic!("some other error was thrown");
+        };
+    }
+
+    #[test]
+    fn should_error_when_parent_directory_missing() {
+        let directories = HashMap::from([(DirectoryId(1), ("a".to_owned(), DirectoryId(7)))]);
+        let options = ParseOptions::default();
+        let mut directory_paths = DirectoryPaths::new(&directories, &options);
+
+        let Err(error) = directory_paths.resolve(DirectoryId(1)) else {
+            panic!("did not throw an error");
+        };
+        let ManifestError::FileParseError(..) = error else {
+            panic!("some other error was thrown");
+        };
+
+        let no_directories = HashMap::new();
+        let Err(error) = DirectoryPaths::new(&no_directories, &options).resolve(DirectoryId(5))
+        else {
+            panic!("did not throw an error");
+        };
+        let ManifestError::FileParseError(..) = error else {
+            panic!("some other error was thrown");
+        };
+    }
+
+    #[test]
+    fn should_error_before_memoizing_when_directory_chain_too_deep() {
+        let directories: HashMap<_, _> = (1..=10_000)
+            .map(|id| (DirectoryId(id), ("dir".to_owned(), DirectoryId(id - 1))))
+            .collect();
+        let options = ParseOptions::new().with_max_directory_depth(Some(8));
+        let mut directory_paths = DirectoryPaths::new(&directories, &options);
+
+        let Err(error) = directory_paths.resolve(DirectoryId(10_000)) else {
+            panic!("did not throw an error");
+        };
+        let ManifestError::DirectoryDepthLimitExceeded(..) = error else {
+            panic!("some other error was thrown");
+        };
+        assert_eq!(directory_paths.resolved.len(), 1, "paths were memoized");
+
+        let (path, depth) = directory_paths.resolve(DirectoryId(8)).unwrap();
+        assert_eq!(path.len(), 8 * "dir/".len(), "path did not match");
+        assert_eq!(depth, 8, "depth did not match");
+
+        let options = ParseOptions::new().with_max_path_length(Some(4 * "dir/".len()));
+        let Err(error) = DirectoryPaths::new(&directories, &options).resolve(DirectoryId(10_000))
+        else {
+            panic!("did not throw an error");
+        };
+        let ManifestError::PathLengthLimitExceeded(..) = error else {
+            panic!("some other error was thrown");
+        };
     }
 }
@@ -1,7 +1,18 @@
+//! Flatbuffer accessors generated from [rman-schema][rman-schema].
+//!
+//! These borrow the decompressed flatbuffer, and are returned by
+//! [`ManifestView`][crate::ManifestView]. If you need owned data, see [entries][crate::entries].
+//!
+//! [rman-schema]: https://github.com/ev3nvy/rman-schema
+
 #[allow(warnings)]
-#[allow(missing_debug_implementations, missing_copy_implementations)]
+#[allow(missing_docs, missing_debug_implementations, missing_copy_implementations)]
 #[allow(clippy::all, clippy::pedantic, clippy::nursery, clippy::unwrap_used)]
 #[rustfmt::skip]
 mod schema_generated;
 
-pub use schema_generated::rman;
+pub(crate) use schema_generated::rman;
+
+pub use schema_generated::rman::{
+    Bundle, Chunk, ChunkingParam, Directory, File, Key, Manifest, Tag,
+};
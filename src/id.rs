@@ -0,0 +1,159 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ManifestError;
+
+macro_rules! id_type {
+    ($(#[$attr:meta])* $name:ident, $kind:literal) => {
+        $(#[$attr])*
+        ///
+        /// Ids are [displayed](std::fmt::Display) as 16 uppercase hex digits (e.g.
+        /// `0123456789ABCDEF`), the same way Riot displays them, and can be
+        /// [parsed](std::str::FromStr) from such hex strings.
+        ///
+        /// If the `serde` feature is enabled, the id is serialized as an integer. Use
+        /// [`serde_hex`][crate::serde_hex] to serialize it as a hex string instead.
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name(pub i64);
+
+        impl $name {
+            /// Creates the id from its raw value.
+            #[must_use]
+            pub const fn new(id: i64) -> Self {
+                Self(id)
+            }
+
+            /// Returns the raw value of the id.
+            #[must_use]
+            pub const fn get(self) -> i64 {
+                self.0
+            }
+        }
+
+        impl From<i64> for $name {
+            fn from(id: i64) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for i64 {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{:016X}", self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ManifestError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                parse_hex(s, $kind).map(Self)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $name {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_i64(self.0)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for $name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                <i64 as serde::Deserialize>::deserialize(deserializer).map(Self)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl crate::serde_hex::sealed::Sealed for $name {}
+
+        #[cfg(feature = "serde")]
+        impl crate::serde_hex::HexId for $name {
+            fn to_bits(self) -> u64 {
+                crate::serde_hex::HexId::to_bits(self.0)
+            }
+
+            fn from_bits(bits: u64) -> Self {
+                Self(crate::serde_hex::HexId::from_bits(bits))
+            }
+        }
+    };
+}
+
+id_type!(
+    /// Id of a [bundle entry][crate::entries::BundleEntry].
+    BundleId,
+    "bundle"
+);
+id_type!(
+    /// Id of a [chunk entry][crate::entries::ChunkEntry].
+    ChunkId,
+    "chunk"
+);
+id_type!(
+    /// Id of a [file entry][crate::entries::FileEntry].
+    FileId,
+    "file"
+);
+id_type!(
+    /// Id of a [directory entry][crate::entries::DirectoryEntry].
+    DirectoryId,
+    "directory"
+);
+
+impl DirectoryId {
+    /// Id of the root directory.
+    ///
+    /// Files and directories whose (parent) directory id is equal to this are placed directly
+    /// in the install root.
+    pub const ROOT: Self = Self(0);
+}
+
+fn parse_hex(s: &str, kind: &str) -> Result<i64, ManifestError> {
+    if s.is_empty() || s.len() > 16 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(ManifestError::InvalidId(format!(
+            "invalid {kind} id \"{s}\""
+        )));
+    }
+    let Ok(id) = u64::from_str_radix(s, 16) else {
+        return Err(ManifestError::InvalidId(format!(
+            "invalid {kind} id \"{s}\""
+        )));
+    };
+    Ok(i64::from_le_bytes(id.to_le_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_display_and_parse_as_hex() {
+        let id = ChunkId(0x0123_4567_89AB_CDEF);
+        assert_eq!(id.to_string(), "0123456789ABCDEF");
+        assert_eq!("0123456789ABCDEF".parse::<ChunkId>().unwrap(), id);
+        assert_eq!("ff".parse::<ChunkId>().unwrap(), ChunkId(0xFF));
+
+        let id = BundleId(-1);
+        assert_eq!(id.to_string(), "FFFFFFFFFFFFFFFF");
+        assert_eq!(id.to_string().parse::<BundleId>().unwrap(), id);
+    }
+
+    #[test]
+    fn should_error_when_parsing_invalid_id() {
+        for invalid in ["", "+1", "-1", "0123456789ABCDEF0", "not hex"] {
+            let Err(error) = invalid.parse::<FileId>() else {
+                panic!("did not throw an error");
+            };
+            let ManifestError::InvalidId(..) = error else {
+                panic!("some other error was thrown");
+            };
+        }
+    }
+}
@@ -74,6 +74,62 @@
 //! # }
 //! ```
 //!
+//! # Example: parsing a manifest file from bytes
+//!
+//! If the whole file is already in memory (e.g. it was downloaded over HTTP), you can call
+//! [`from_bytes`](crate::RiotManifest::from_bytes), which avoids wrapping the bytes in a
+//! [`Cursor`][std::io::Cursor]. For readers that can't [seek][std::io::Seek] at all, there is
+//! [`from_stream`](crate::RiotManifest::from_stream).
+//!
+//! ```rust
+//! use std::fs;
+//!
+//! # use rman::Result;
+//! use rman::RiotManifest;
+//!
+//! # fn main() -> Result<()> {
+//! let path = "file.manifest";
+//!   # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+//! let bytes = fs::read(path)?;
+//!
+//! let manifest = RiotManifest::from_bytes(&bytes, None)?;
+//! assert_eq!(manifest.data.files.len(), 1);
+//!
+//! let manifest = RiotManifest::from_stream(&bytes[..], None)?;
+//! assert_eq!(manifest.data.files.len(), 1);
+//!   # Ok(())
+//! # }
+//! ```
+//!
+//! # Example: parsing a manifest file from url
+//!
+//! Manifests can also be downloaded and parsed in one step with
+//! [`from_url`](crate::RiotManifest::from_url), or read from any [`AsyncRead`][async-read] with
+//! [`from_async_reader`](crate::RiotManifest::from_async_reader).
+//!
+//! ```rust
+//! use std::fs;
+//!
+//! # use httptest::{matchers::*, responders::*, Expectation, Server};
+//! use rman::{Result, RiotManifest};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     # let manifest = fs::read(concat!(env!("OUT_DIR"), "/valid.manifest")).unwrap();
+//!     # let server = Server::run();
+//!     # server.expect(
+//!         # Expectation::matching(request::method_path("GET", "/file.manifest"))
+//!         # .respond_with(status_code(200).body(manifest)),
+//!     # );
+//!     let url = "https://valorant.secure.dyn.riotcdn.net/channels/public/releases/file.manifest";
+//!     # let url = server.url("/file.manifest").to_string();
+//!     let manifest = RiotManifest::from_url(url, None).await?;
+//!
+//!     assert_eq!(manifest.data.files.len(), 1);
+//!     Ok(())
+//! }
+//! ```
+//!
 //! # Example: downloading a file
 //!
 //! To download a specific file from a parsed manifest, you can invoke the
@@ -127,6 +183,180 @@
 //! }
 //! ```
 //!
+//! # Example: downloading multiple files with a shared client
+//!
+//! [`download`][crate::File::download] creates a new [`Client`][reqwest::Client] on every call.
+//! When downloading multiple files, it's better to build a single client (optionally from a
+//! [`DownloadConfig`]) and pass it to [`download_with_client`][crate::File::download_with_client],
+//! so that connections can be reused.
+//!
+//! ```rust
+//! use std::fs;
+//! use std::time::Duration;
+//!
+//! # use httptest::{matchers::*, responders::*, Expectation, Server};
+//! use rman::{DownloadConfig, Result, RiotManifest};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     # let bundle = fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+//!     # let server = Server::run();
+//!     # server.expect(
+//!         # Expectation::matching(request::method_path(
+//!             # "GET",
+//!             # "/bundles/0000000000000000.bundle",
+//!         # ))
+//!         # .respond_with(
+//!             # status_code(200)
+//!                 # .body(bundle)
+//!                 # .append_header("Content-Type", "binary/octet-stream")
+//!                 # .append_header("Content-Length", 13),
+//!         # ),
+//!     # );
+//!     let path = "file.manifest";
+//!     # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+//!     let manifest = RiotManifest::from_path(path, None)?;
+//!
+//!     let config = DownloadConfig {
+//!         timeout: Some(Duration::from_secs(60)),
+//!         user_agent: Some("my-patcher/1.0".to_owned()),
+//!         ..Default::default()
+//!     };
+//!     let client = config.build_client()?;
+//!
+//!     let url = "https://valorant.secure.dyn.riotcdn.net/channels/public/bundles";
+//!     # let url = server.url("/bundles").to_string();
+//!
+//!     for file in &manifest.data.files {
+//!         let mut buf = Vec::new();
+//!         file.download_with_client(&client, &mut buf, url.as_str()).await?;
+//!         # assert_eq!(buf.len(), 4);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Example: reporting download progress
+//!
+//! A [`Downloader`] can report [progress events][ProgressEvent] to a [`ProgressSink`], which is
+//! implemented for closures. The same downloader can also
+//! [download multiple files][crate::Downloader::download_files] into a directory, in which case
+//! [`overall_progress`](crate::ProgressEvent::overall_progress) covers all of them.
+//!
+//! ```rust
+//! use std::fs;
+//!
+//! # use httptest::{matchers::*, responders::*, Expectation, Server};
+//! use rman::{DownloadConfig, Downloader, ProgressEvent, ProgressEventKind, Result, RiotManifest};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     # let bundle = fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+//!     # let server = Server::run();
+//!     # server.expect(
+//!         # Expectation::matching(request::method_path(
+//!             # "GET",
+//!             # "/bundles/0000000000000000.bundle",
+//!         # ))
+//!         # .respond_with(
+//!             # status_code(200)
+//!                 # .body(bundle)
+//!                 # .append_header("Content-Type", "binary/octet-stream")
+//!                 # .append_header("Content-Length", 13),
+//!         # ),
+//!     # );
+//!     let path = "file.manifest";
+//!     # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+//!     let manifest = RiotManifest::from_path(path, None)?;
+//!
+//!     let config = DownloadConfig {
+//!         max_retries: 3,
+//!         ..Default::default()
+//!     };
+//!     let downloader = Downloader::from_config(&config)?.with_progress(|event: &ProgressEvent| {
+//!         if let ProgressEventKind::ChunkCompleted { .. } = event.kind {
+//!             let progress = event.overall_progress;
+//!             println!("{}/{} bytes", progress.written_bytes, progress.total_bytes);
+//!         }
+//!     });
+//!
+//!     let url = "https://valorant.secure.dyn.riotcdn.net/channels/public/bundles";
+//!     # let url = server.url("/bundles").to_string();
+//!
+//!     let root = "install";
+//!     # let root = concat!(env!("OUT_DIR"), "/install");
+//!     downloader.download_files(&manifest.data.files, root, url).await?;
+//!
+//!     # assert_eq!(fs::read(format!("{root}/Test/file.txt"))?.len(), 4);
+//!     Ok(())
+//! }
+//! ```
+//!
+//! # Example: resuming a download
+//!
+//! A download can be stopped with a [`CancellationToken`]. If the files are downloaded with
+//! [`download_files_resumable`][crate::Downloader::download_files_resumable], the written chunks
+//! are recorded in a [`DownloadState`], and calling the function again skips them.
+//!
+//! ```rust
+//! use std::fs;
+//!
+//! # use httptest::{matchers::*, responders::*, Expectation, Server};
+//! use rman::{CancellationToken, Downloader, DownloadState, ManifestError, Result, RiotManifest};
+//!
+//! #[tokio::main]
+//! async fn main() -> Result<()> {
+//!     # let bundle = fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+//!     # let server = Server::run();
+//!     # server.expect(
+//!         # Expectation::matching(request::method_path(
+//!             # "GET",
+//!             # "/bundles/0000000000000000.bundle",
+//!         # ))
+//!         # .respond_with(
+//!             # status_code(200)
+//!                 # .body(bundle)
+//!                 # .append_header("Content-Type", "binary/octet-stream")
+//!                 # .append_header("Content-Length", 13),
+//!         # ),
+//!     # );
+//!     let path = "file.manifest";
+//!     # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+//!     let manifest = RiotManifest::from_path(path, None)?;
+//!
+//!     let url = "https://valorant.secure.dyn.riotcdn.net/channels/public/bundles";
+//!     # let url = server.url("/bundles").to_string();
+//!     let root = "install";
+//!     # let root = concat!(env!("OUT_DIR"), "/resume");
+//!     let state_path = "install.state";
+//!     # let state_path = concat!(env!("OUT_DIR"), "/resume.state");
+//!
+//!     let token = CancellationToken::new();
+//!     let downloader = Downloader::new(reqwest::Client::new()).with_cancellation(token.clone());
+//!     let mut state = DownloadState::open(state_path, manifest.header.manifest_id)?;
+//!
+//!     // cancel the download, e.g. from another task
+//!     token.cancel();
+//!
+//!     let result = downloader
+//!         .download_files_resumable(&manifest.data.files, root, url.as_str(), &mut state)
+//!         .await;
+//!     assert!(matches!(result, Err(ManifestError::Cancelled)));
+//!
+//!     // later, resume the download
+//!     let downloader = Downloader::new(reqwest::Client::new());
+//!     let mut state = DownloadState::open(state_path, manifest.header.manifest_id)?;
+//!     downloader
+//!         .download_files_resumable(&manifest.data.files, root, url.as_str(), &mut state)
+//!         .await?;
+//!     state.remove()?;
+//!
+//!     # assert_eq!(fs::read(format!("{root}/Test/file.txt"))?.len(), 4);
+//!     Ok(())
+//! }
+//! ```
+//!
 //! # Scope
 //!
 //! This crate:
@@ -135,10 +365,16 @@
 //! - parses the decompressed [flatbuffer data][flatbuffers],
 //! - stores all of the parsed data on [`ManifestData`],
 //! - combines the data into a vector of downloadable [`File`]s,
-//! - provides a function to [`download`][crate::File::download] specific files.
+//! - provides a function to [`download`][crate::File::download] specific files, optionally
+//!   using a shared, [configurable][crate::DownloadConfig] http client,
+//! - provides a [`Downloader`] that reports progress and can download multiple files at once,
+//!   as well as cancel, resume and [rate limit][crate::RateLimiter] the download, and
+//!   [cache][crate::ChunkCache] downloaded chunks,
+//! - [writes][crate::RiotManifest::to_bytes] parsed (and possibly modified) data back into a
+//!   `.manifest` file.
 //!
 //! This crate doesn't:
-//! - generate a `.manifest` file,
+//! - generate a `.manifest` file from game files,
 //! - create or parse chunks.
 //!
 //! # Feature: `default`
@@ -147,13 +383,33 @@
 //!
 //! # Feature: `version_error`
 //!
-//! If enabled, throws errors on unknown manifest versions, instead of continuing and assuming it
-//! works.
+//! If enabled, the [default version policy](crate::VersionPolicy::default) is
+//! [`Reject`](crate::VersionPolicy::Reject), which throws errors on unknown manifest versions,
+//! instead of continuing and assuming it works.
+//!
+//! Since this changes the default for every crate in the dependency graph, prefer setting the
+//! policy at runtime with [`ParseOptions::with_version_policy`][crate::ParseOptions::with_version_policy].
 //!
 //! # Feature: `serde`
 //!
-//! If enabled, all structs in [`entries`], as well as [`File`]
-//! will implement [`Serialize`][serde-serialize] and [`Deserialize`][serde-deserialize].
+//! If enabled, [`RiotManifest`], [`Header`], [`ManifestData`], [`Diagnostic`], all structs in
+//! [`entries`], as well as [`File`] and [`FileChunk`] will implement
+//! [`Serialize`][serde-serialize] and [`Deserialize`][serde-deserialize].
+//!
+//! Ids are serialized as integers. Fields of your own types can serialize ids as hex strings
+//! with [`serde_hex`]. Together with [`RiotManifest::to_bytes`], this allows editing a manifest
+//! as JSON and writing it back.
+//!
+//! # Feature: `mmap`
+//!
+//! If enabled, [`RiotManifest::from_mmap`][crate::RiotManifest::from_mmap] can be used to
+//! memory-map a manifest file, instead of reading it into memory.
+//!
+//! # Feature: `rayon`
+//!
+//! If enabled, entries are converted, and [file entries][crate::entries::FileEntry] are
+//! resolved into [`File`]s in parallel, using [rayon][rayon]. The output is the same as without
+//! the feature, including the order of the files.
 //!
 //! # Feature: `native-tls`
 //!
@@ -164,21 +420,40 @@
 //! If enabled, the feature with the same name is enabled for [`reqwest`].
 //!
 //! [flatbuffers]: https://github.com/google/flatbuffers
+//! [async-read]: https://docs.rs/tokio/latest/tokio/io/trait.AsyncRead.html
 //! [manifest]: https://technology.riotgames.com/news/supercharging-data-delivery-new-league-patcher
+//! [rayon]: https://github.com/rayon-rs/rayon
 //! [riot-games]: https://www.riotgames.com
 //! [rman-crates-io]: https://crates.io/crates/rman
 //! [serde-serialize]: https://docs.rs/serde/latest/serde/trait.Serialize.html
 //! [serde-deserialize]: https://docs.rs/serde/latest/serde/trait.Deserialize.html
 //! [zstd]: https://github.com/facebook/zstd
 
+mod download;
 pub mod entries;
 mod error;
 mod file;
-mod generated;
+pub mod generated;
+mod id;
 mod parser;
+mod path;
+#[cfg(feature = "serde")]
+pub mod serde_hex;
 
+pub use crate::download::cache::{CachedChunk, ChunkCache, ChunkCacheMode};
+pub use crate::download::cancel::CancellationToken;
+pub use crate::download::config::DownloadConfig;
+pub use crate::download::limiter::RateLimiter;
+pub use crate::download::progress::{Progress, ProgressEvent, ProgressEventKind, ProgressSink};
+pub use crate::download::state::DownloadState;
+pub use crate::download::Downloader;
 pub use crate::error::{ManifestError, Result};
-pub use crate::file::File;
-pub use crate::parser::header::Header;
+pub use crate::file::{File, FileChunk, FilePermissions};
+pub use crate::id::{BundleId, ChunkId, DirectoryId, FileId};
+pub use crate::parser::header::{Header, HeaderFlags};
 pub use crate::parser::manifest::ManifestData;
+pub use crate::parser::options::{ParseOptions, VersionPolicy};
+pub use crate::parser::validate::Diagnostic;
+pub use crate::parser::view::{DecompressedManifest, ManifestView};
 pub use crate::parser::RiotManifest;
+pub use crate::path::{ManifestPath, PathStrictness};
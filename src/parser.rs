@@ -1,21 +1,43 @@
 pub mod header;
 pub mod manifest;
+pub mod options;
+pub mod validate;
+pub mod view;
 
 use header::Header;
 use manifest::ManifestData;
+use options::{ParseOptions, VersionPolicy};
+use view::DecompressedManifest;
 
 use std::fs;
-use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
 use std::path::Path;
 
 use log::debug;
+use reqwest::{Client, IntoUrl};
+use tokio::io::{AsyncRead, AsyncReadExt};
 
 use crate::{ManifestError, Result};
 
+/// Size of the [`Header`] in bytes.
+const HEADER_SIZE: u64 = 28;
+
+/// Maximum amount of bytes allocated up front based on sizes from the header.
+///
+/// The sizes can't be trusted until the data is actually read, so buffers larger than this grow
+/// as the data arrives instead.
+const MAX_PREALLOCATED_SIZE: usize = 4 * 1024 * 1024;
+
 /// Main parser object.
 ///
 /// Depending on the function you call, it either parses a manifest
-/// [from reader][crate::RiotManifest::from_reader] or [a file][crate::RiotManifest::from_path].
+/// [from reader][crate::RiotManifest::from_reader], [a file][crate::RiotManifest::from_path],
+/// [bytes][crate::RiotManifest::from_bytes], [a stream][crate::RiotManifest::from_stream],
+/// [an async reader][crate::RiotManifest::from_async_reader] or
+/// [a url][crate::RiotManifest::from_url].
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+// the only unsafe method is `from_mmap`, which does not rely on any invariants of the fields
+#[cfg_attr(feature = "serde", allow(clippy::unsafe_derive_deserialize))]
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct RiotManifest {
     /// Parsed file header data.
@@ -49,13 +71,10 @@ impl RiotManifest {
     /// [parsing a manifest file from path](index.html#example-parsing-a-manifest-file-from-path).
     ///
     /// [`RiotManifest::from_reader`]: crate::RiotManifest::from_reader
-    pub fn from_path<P: AsRef<Path>>(
-        path: P,
-        flatbuffer_verifier_options: Option<&flatbuffers::VerifierOptions>,
-    ) -> Result<Self> {
+    pub fn from_path<P: AsRef<Path>>(path: P, options: Option<&ParseOptions>) -> Result<Self> {
         let file = fs::File::open(path)?;
         let mut reader = BufReader::new(file);
-        Self::from_reader(&mut reader, flatbuffer_verifier_options)
+        Self::from_reader(&mut reader, options)
     }
 
     /// Main parser method.
@@ -63,10 +82,10 @@ impl RiotManifest {
     /// Brief overview on how parsing the manifest is done:
     /// - attempts to [parse the header][crate::Header::from_reader]
     /// - [seeks][std::io::Seek] to the [offset](crate::Header::offset)
-    /// - reads [x amount](crate::Header::compressed_size) of bytes to buffer
-    /// - [decompresses][zstd::bulk::decompress] read bytes
+    /// - [streams][zstd::Decoder] [x amount](crate::Header::compressed_size) of bytes through a
+    ///   zstd decoder, so the compressed data is never fully loaded into memory
     /// - decompressed data is a [flatbuffer binary], that is then
-    /// [parsed][crate::ManifestData::parse].
+    ///   [parsed][crate::ManifestData::parse].
     ///
     /// # Errors
     ///
@@ -76,6 +95,12 @@ impl RiotManifest {
     /// If seeking to offset fails, the error [`SeekError`][crate::ManifestError::SeekError] is
     /// returned.
     ///
+    /// If the compressed or uncompressed size exceeds the limits set in
+    /// [`ParseOptions`][crate::ParseOptions], the error
+    /// [`CompressedSizeLimitExceeded`][crate::ManifestError::CompressedSizeLimitExceeded] or
+    /// [`UncompressedSizeLimitExceeded`][crate::ManifestError::UncompressedSizeLimitExceeded] is
+    /// returned.
+    ///
     /// If converting [`compressed_size`](crate::Header::compressed_size) or
     /// [`uncompressed_size`](crate::Header::uncompressed_size) to [`usize`] fails, the error
     /// [`ConversionFailure`][crate::ManifestError::ConversionFailure] is returned.
@@ -86,38 +111,425 @@ impl RiotManifest {
     /// If zstd decompression fails, the error
     /// [`ZstdDecompressError`][crate::ManifestError::ZstdDecompressError] is returned.
     ///
+    /// If the size of decompressed data does not equal the
+    /// [uncompressed size](crate::Header::uncompressed_size), the error
+    /// [`UncompressedSizeMismatch`][crate::ManifestError::UncompressedSizeMismatch] is
+    /// returned.
+    ///
     /// If parsing flatbuffer binary fails, it propagates an error from
     /// [`ManifestData::parse`][crate::ManifestData::parse].
     ///
     /// [flatbuffer binary]: https://github.com/ev3nvy/rman-schema
     pub fn from_reader<R: Read + Seek>(
         mut reader: R,
-        flatbuffer_verifier_options: Option<&flatbuffers::VerifierOptions>,
+        options: Option<&ParseOptions>,
     ) -> Result<Self> {
-        let header = Header::from_reader(&mut reader)?;
+        let size = Header::stream_size(&mut reader)?;
+        let header = Self::parse_header(&mut reader, Some(size), options)?;
 
         if let Err(error) = reader.seek(SeekFrom::Start(header.offset.into())) {
             return Err(ManifestError::SeekError(error));
-        };
+        }
+
+        Self::check_header(&header, options)?;
+
+        let decompressed = Self::decompress_stream(&header, &mut reader)?;
+        let data = ManifestData::parse(&decompressed, options)?;
+
+        Ok(Self { header, data })
+    }
+
+    /// Parses a manifest that is already fully loaded in memory.
+    ///
+    /// Works the same as [`RiotManifest::from_reader`], except that the compressed data is
+    /// decompressed straight from the provided slice, without copying it into a separate buffer.
+    ///
+    /// # Errors
+    ///
+    /// If parsing the header fails, it propagates an error from
+    /// [`Header::from_reader_with_size`][crate::Header::from_reader_with_size].
+    ///
+    /// Otherwise, see [`RiotManifest::from_reader`], excluding the
+    /// [`SeekError`][crate::ManifestError::SeekError].
+    ///
+    /// [`RiotManifest::from_reader`]: crate::RiotManifest::from_reader
+    pub fn from_bytes(bytes: &[u8], options: Option<&ParseOptions>) -> Result<Self> {
+        Self::decompress(bytes, options)?.parse(options)
+    }
+
+    /// Parses the header of a manifest that is already fully loaded in memory, and decompresses
+    /// the flatbuffer binary, without parsing it.
+    ///
+    /// The decompressed data can be accessed through a [`ManifestView`][crate::ManifestView],
+    /// which provides access to the flatbuffer without copying it.
+    ///
+    /// # Errors
+    ///
+    /// See [`RiotManifest::from_bytes`], excluding the errors from
+    /// [`ManifestData::parse`][crate::ManifestData::parse].
+    ///
+    /// [`RiotManifest::from_bytes`]: crate::RiotManifest::from_bytes
+    pub fn decompress(
+        bytes: &[u8],
+        options: Option<&ParseOptions>,
+    ) -> Result<DecompressedManifest> {
+        let header = Self::parse_header(bytes, Some(bytes.len() as u64), options)?;
+        Self::check_header(&header, options)?;
+
+        debug!("Attempting to convert \"offset\" and \"compressed_size\" into \"usize\".");
+        let offset: usize = header.offset.try_into()?;
+        let compressed_size: usize = header.compressed_size.try_into()?;
+        debug!("Successfully converted \"offset\" and \"compressed_size\" into \"usize\".");
+
+        // header checks guarantee that the compressed data is within bounds
+        let compressed = &bytes[offset..offset + compressed_size];
+        let buffer = Self::decompress_stream(&header, compressed)?;
+
+        Ok(DecompressedManifest { header, buffer })
+    }
+
+    /// Memory-maps a file, and decompresses the flatbuffer binary straight from the mapped
+    /// memory, without parsing it.
+    ///
+    /// The file is only mapped until the data is decompressed. Use
+    /// [`DecompressedManifest::view`][crate::DecompressedManifest::view] to access the
+    /// flatbuffer lazily, or [`DecompressedManifest::parse`][crate::DecompressedManifest::parse]
+    /// to parse it.
+    ///
+    /// The file must not be modified while it is mapped, otherwise the behaviour is undefined.
+    ///
+    /// # Errors
+    ///
+    /// If opening or mapping the file fails, the error
+    /// [`IoError`][crate::ManifestError::IoError] is returned.
+    ///
+    /// Otherwise, see [`RiotManifest::decompress`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rman::Result;
+    /// use rman::RiotManifest;
+    ///
+    /// # fn main() -> Result<()> {
+    /// let path = "file.manifest";
+    ///   # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    /// let decompressed = RiotManifest::from_mmap(path, None)?;
+    /// let view = decompressed.view(None)?;
+    ///
+    /// for file in view.files() {
+    ///     println!("{}", view.file_path(&file)?);
+    /// }
+    ///   # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`RiotManifest::decompress`]: crate::RiotManifest::decompress
+    #[cfg(feature = "mmap")]
+    pub fn from_mmap<P: AsRef<Path>>(
+        path: P,
+        options: Option<&ParseOptions>,
+    ) -> Result<DecompressedManifest> {
+        let file = fs::File::open(path)?;
+        // SAFETY: the map is only read while decompressing, and modifying the file during that
+        // time is documented as undefined behaviour
+        let map = unsafe { memmap2::Mmap::map(&file)? };
+
+        Self::decompress(&map, options)
+    }
+
+    /// Parses a manifest from a reader that can't [seek][std::io::Seek].
+    ///
+    /// The header and the compressed data are read sequentially. If the
+    /// [offset](crate::Header::offset) is larger than the size of the header (28 bytes), the
+    /// bytes in between are read and discarded.
+    ///
+    /// Since the size of the stream is unknown, the header can't be checked against it (see
+    /// [`Header::from_stream`][crate::Header::from_stream]), and a truncated stream is only
+    /// detected when reading the compressed data.
+    ///
+    /// # Errors
+    ///
+    /// If parsing the header fails, it propagates an error from
+    /// [`Header::from_stream`][crate::Header::from_stream].
+    ///
+    /// If the stream ends before all of the compressed data is read, the error
+    /// [`IoError`][crate::ManifestError::IoError] is returned.
+    ///
+    /// Otherwise, see [`RiotManifest::from_reader`], excluding the
+    /// [`SeekError`][crate::ManifestError::SeekError].
+    ///
+    /// [`RiotManifest::from_reader`]: crate::RiotManifest::from_reader
+    pub fn from_stream<R: Read>(mut reader: R, options: Option<&ParseOptions>) -> Result<Self> {
+        let header = Self::parse_header(&mut reader, None, options)?;
+
+        let padding = u64::from(header.offset) - HEADER_SIZE;
+        if padding > 0 {
+            debug!("Skipping {padding} bytes between the header and the compressed data.");
+            let skipped = io::copy(&mut reader.by_ref().take(padding), &mut io::sink())?;
+            if skipped != padding {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        }
+
+        Self::check_header(&header, options)?;
+
+        let decompressed = Self::decompress_stream(&header, &mut reader)?;
+        let data = ManifestData::parse(&decompressed, options)?;
+
+        Ok(Self { header, data })
+    }
+
+    /// Parses a manifest from an [`AsyncRead`].
+    ///
+    /// This is the asynchronous counterpart of [`RiotManifest::from_stream`]: the header and
+    /// the compressed data are read sequentially, after which the data is decompressed and
+    /// parsed.
+    ///
+    /// # Errors
+    ///
+    /// See [`RiotManifest::from_stream`].
+    ///
+    /// [`RiotManifest::from_stream`]: crate::RiotManifest::from_stream
+    pub async fn from_async_reader<R: AsyncRead + Unpin + Send>(
+        mut reader: R,
+        options: Option<&ParseOptions>,
+    ) -> Result<Self> {
+        let mut buf = [0u8; 28];
+        reader.read_exact(&mut buf).await?;
+        let header = Self::parse_header(&buf[..], None, options)?;
+
+        let padding = u64::from(header.offset) - HEADER_SIZE;
+        if padding > 0 {
+            debug!("Skipping {padding} bytes between the header and the compressed data.");
+            let skipped =
+                tokio::io::copy(&mut (&mut reader).take(padding), &mut tokio::io::sink()).await?;
+            if skipped != padding {
+                return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+            }
+        }
+
+        Self::check_header(&header, options)?;
 
         debug!("Attempting to convert \"compressed_size\" into \"usize\".");
         let compressed_size: usize = header.compressed_size.try_into()?;
         debug!("Successfully converted \"compressed_size\" into \"usize\".");
 
-        let mut buf = vec![0u8; compressed_size];
-        reader.read_exact(&mut buf)?;
+        let mut buf = Vec::with_capacity(compressed_size.min(MAX_PREALLOCATED_SIZE));
+        (&mut reader)
+            .take(header.compressed_size.into())
+            .read_to_end(&mut buf)
+            .await?;
+        if buf.len() != compressed_size {
+            return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into());
+        }
+
+        Self::from_compressed(header, &buf, options)
+    }
+
+    /// Downloads a manifest from the provided url and parses it.
+    ///
+    /// This is just a convenience method that calls [`RiotManifest::from_url_with_client`] with
+    /// a new [`Client`].
+    ///
+    /// # Errors
+    ///
+    /// See [`RiotManifest::from_url_with_client`].
+    ///
+    /// [`RiotManifest::from_url_with_client`]: crate::RiotManifest::from_url_with_client
+    pub async fn from_url<U: IntoUrl + Send>(
+        url: U,
+        options: Option<&ParseOptions>,
+    ) -> Result<Self> {
+        Self::from_url_with_client(&Client::new(), url, options).await
+    }
+
+    /// Downloads a manifest from the provided url using the provided [`Client`], and parses it.
+    ///
+    /// The response body is loaded into memory and parsed with [`RiotManifest::from_bytes`].
+    ///
+    /// # Errors
+    ///
+    /// If downloading fails, or the server responds with an error status code, the error
+    /// [`ReqwestError`][crate::ManifestError::ReqwestError] is returned.
+    ///
+    /// If parsing fails, it propagates an error from [`RiotManifest::from_bytes`].
+    ///
+    /// # Examples
+    ///
+    /// See [parsing a manifest file from url](index.html#example-parsing-a-manifest-file-from-url).
+    ///
+    /// [`RiotManifest::from_bytes`]: crate::RiotManifest::from_bytes
+    pub async fn from_url_with_client<U: IntoUrl + Send>(
+        client: &Client,
+        url: U,
+        options: Option<&ParseOptions>,
+    ) -> Result<Self> {
+        let response = client.get(url).send().await?.error_for_status()?;
+        let bytes = response.bytes().await?;
+
+        Self::from_bytes(&bytes, options)
+    }
 
+    /// Writes the manifest into the `.manifest` file format.
+    ///
+    /// The [entries][crate::entries] are serialized with [`ManifestData::to_flatbuffer`],
+    /// compressed with zstd and prefixed with the [`Header`] (see
+    /// [`Header::write_to`][crate::Header::write_to]). The [offset](crate::Header::offset),
+    /// [compressed size](crate::Header::compressed_size) and
+    /// [uncompressed size](crate::Header::uncompressed_size) are recalculated, while the rest of
+    /// the header fields are written as they are.
+    ///
+    /// # Errors
+    ///
+    /// If zstd compression fails, the error
+    /// [`ZstdCompressError`][crate::ManifestError::ZstdCompressError] is returned.
+    ///
+    /// If the size of compressed or uncompressed data does not fit into [`u32`], the error
+    /// [`ConversionFailure`][crate::ManifestError::ConversionFailure] is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rman::Result;
+    /// use rman::RiotManifest;
+    ///
+    /// # fn main() -> Result<()> {
+    /// let path = "file.manifest";
+    ///   # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    /// let manifest = RiotManifest::from_path(path, None)?;
+    ///
+    /// let bytes = manifest.to_bytes()?;
+    /// assert_eq!(RiotManifest::from_bytes(&bytes, None)?.data, manifest.data);
+    ///   # Ok(())
+    /// # }
+    /// ```
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let flatbuffer = self.data.to_flatbuffer();
+        let compressed = match zstd::bulk::compress(&flatbuffer, 0) {
+            Ok(result) => result,
+            Err(error) => return Err(ManifestError::ZstdCompressError(error)),
+        };
+
+        let header = Header {
+            offset: HEADER_SIZE.try_into()?,
+            compressed_size: compressed.len().try_into()?,
+            uncompressed_size: flatbuffer.len().try_into()?,
+            ..self.header
+        };
+
+        let mut bytes = Vec::with_capacity(usize::try_from(HEADER_SIZE)? + compressed.len());
+        header.write_to(&mut bytes)?;
+        bytes.extend_from_slice(&compressed);
+
+        Ok(bytes)
+    }
+
+    fn parse_header<R: Read>(
+        reader: R,
+        size: Option<u64>,
+        options: Option<&ParseOptions>,
+    ) -> Result<Header> {
+        match options {
+            Some(options) => Header::parse(reader, size, options.version_policy()),
+            None => Header::parse(reader, size, &VersionPolicy::default()),
+        }
+    }
+
+    fn check_header(header: &Header, options: Option<&ParseOptions>) -> Result<()> {
+        let default_options = ParseOptions::default();
+        options.unwrap_or(&default_options).check_header(header)
+    }
+
+    fn from_compressed(
+        header: Header,
+        compressed: &[u8],
+        options: Option<&ParseOptions>,
+    ) -> Result<Self> {
+        let decompressed = Self::decompress_stream(&header, compressed)?;
+        let data = ManifestData::parse(&decompressed, options)?;
+
+        Ok(Self { header, data })
+    }
+
+    fn decompress_stream<R: Read>(header: &Header, reader: R) -> Result<Vec<u8>> {
         debug!("Attempting to convert \"uncompressed_size\" into \"usize\".");
         let uncompressed_size: usize = header.uncompressed_size.try_into()?;
         debug!("Successfully converted \"uncompressed_size\" into \"usize\".");
 
-        let decompressed = match zstd::bulk::decompress(&buf, uncompressed_size) {
-            Ok(result) => result,
+        let reader = ExactReader::new(reader, header.compressed_size.into());
+        let mut decoder = match zstd::Decoder::new(reader) {
+            Ok(decoder) => decoder,
             Err(error) => return Err(ManifestError::ZstdDecompressError(error)),
         };
 
-        let data = ManifestData::parse(&decompressed, flatbuffer_verifier_options)?;
+        // the buffer grows as data arrives, so that a forged uncompressed size can't cause a
+        // huge allocation up front, and one byte more than expected is read, so that too much
+        // data can be detected
+        let mut decompressed = Vec::with_capacity(uncompressed_size.min(MAX_PREALLOCATED_SIZE));
+        let result = (&mut decoder)
+            .take(u64::from(header.uncompressed_size) + 1)
+            .read_to_end(&mut decompressed);
 
-        Ok(Self { header, data })
+        if let Err(error) = result {
+            if decoder.get_ref().get_ref().failed {
+                return Err(ManifestError::IoError(error));
+            }
+            return Err(ManifestError::ZstdDecompressError(error));
+        }
+        Self::check_decompressed_size(header, decompressed.len())?;
+
+        Ok(decompressed)
+    }
+
+    fn check_decompressed_size(header: &Header, size: usize) -> Result<()> {
+        if u64::try_from(size).ok() != Some(header.uncompressed_size.into()) {
+            return Err(ManifestError::UncompressedSizeMismatch(
+                header.uncompressed_size,
+                size,
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Reader that reads exactly `remaining` bytes from the inner reader.
+///
+/// Unlike [`Read::take`], ending early is an error, which is also recorded so that it can be
+/// told apart from errors produced by the zstd decoder.
+struct ExactReader<R> {
+    inner: R,
+    remaining: u64,
+    failed: bool,
+}
+
+impl<R> ExactReader<R> {
+    const fn new(inner: R, remaining: u64) -> Self {
+        Self {
+            inner,
+            remaining,
+            failed: false,
+        }
+    }
+}
+
+impl<R: Read> Read for ExactReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+
+        let max = usize::try_from(self.remaining).map_or(buf.len(), |r| r.min(buf.len()));
+        let result = match self.inner.read(&mut buf[..max]) {
+            Ok(0) if max > 0 => Err(io::Error::from(io::ErrorKind::UnexpectedEof)),
+            result => result,
+        };
+
+        match &result {
+            Ok(read) => self.remaining -= *read as u64,
+            Err(error) if error.kind() != io::ErrorKind::Interrupted => self.failed = true,
+            Err(_) => {}
+        }
+        result
     }
 }
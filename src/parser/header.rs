@@ -1,12 +1,54 @@
-use std::borrow::BorrowMut;
-use std::io::{Read, Seek};
+use std::io::{Read, Seek, SeekFrom, Write};
 
-use byteorder::{ReadBytesExt, LE};
-use log::{debug, info, warn};
+use bitflags::bitflags;
+use byteorder::{ReadBytesExt, WriteBytesExt, LE};
+use log::debug;
 
-use crate::{ManifestError, Result};
+use crate::{ManifestError, Result, VersionPolicy};
+
+bitflags! {
+    /// [Header flags](Header::flags).
+    ///
+    /// What the flags mean is unknown. The only bit that has been observed is named after its
+    /// position, [`UNKNOWN_BIT_9`](HeaderFlags::UNKNOWN_BIT_9).
+    ///
+    /// All bits are preserved when parsing and [writing](Header::write_to) the header, and can
+    /// be accessed with [`bits`](HeaderFlags::bits).
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct HeaderFlags: u16 {
+        /// Bit 9 (`512`), whose meaning is unknown.
+        ///
+        /// Every official, Riot-made manifest so far has only this bit set, and other
+        /// implementations refuse to parse manifests without it.
+        const UNKNOWN_BIT_9 = 1 << 9;
+
+        // other bits have never been seen, but a header should still be written back exactly
+        // as it was read
+        const _ = !0;
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for HeaderFlags {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_u16(self.bits())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for HeaderFlags {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        <u16 as serde::Deserialize>::deserialize(deserializer).map(Self::from_bits_retain)
+    }
+}
 
 /// File header.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 pub struct Header {
     /// Magic bytes of the file.
@@ -18,16 +60,22 @@ pub struct Header {
     ///
     /// So far, it's only ever been 2.
     ///
-    /// NOTE: The library logs if the version differs from 2 using the [log crate][log].
+    /// NOTE: What happens if the version differs from 2 is decided by the
+    /// [`VersionPolicy`][crate::VersionPolicy]. By default, the library logs it using the
+    /// [log crate][log].
     pub major: u8,
     /// Minor version of the manifest format.
     ///
     /// So far, it's only ever been 0.
     ///
-    /// NOTE: The library logs if the version differs from 0 using the [log crate][log].
+    /// NOTE: What happens if the version differs from 0 is decided by the
+    /// [`VersionPolicy`][crate::VersionPolicy]. By default, the library logs it using the
+    /// [log crate][log].
     pub minor: u8,
-    /// Manifest flags (no idea what any of them mean or do).
-    pub flags: u16,
+    /// Manifest flags.
+    ///
+    /// See [`HeaderFlags`] for what is known about them.
+    pub flags: HeaderFlags,
     /// Offset to the compressed flatbuffer data.
     pub offset: u32,
     /// Size of the parsed flatbuffer schema before decompression.
@@ -47,12 +95,12 @@ impl Header {
     /// It checks the [magic bytes](Header::magic), [version](Header::major),
     /// [offset](Header::offset) and [compressed size](Header::compressed_size).
     ///
-    /// # Errors
+    /// The size of the file is determined by [seeking][std::io::Seek] to the end of the reader,
+    /// after which the reader is rewound and the header is read from the start.
     ///
-    /// If converting file size from [`usize`] fails, the error
-    /// [`ConversionFailure`][crate::ManifestError::ConversionFailure] is returned.
+    /// # Errors
     ///
-    /// If seeking to start (rewinding) fails, the error
+    /// If seeking to the end, or to start (rewinding) fails, the error
     /// [`SeekError`][crate::ManifestError::SeekError] is returned.
     ///
     /// If reading from io stream fails, the error [`IoError`][crate::ManifestError::IoError] is
@@ -61,13 +109,13 @@ impl Header {
     /// If magic bytes do not equal to `R`, `M`, `A` and `N`, the error
     /// [`InvalidMagicBytes`][crate::ManifestError::InvalidMagicBytes] is returned.
     ///
-    /// If major version does not equal 2, and the feature
-    /// [`version_error`](index.html#feature-version_error) is enabled, the error
-    /// [`InvalidMajor`][crate::ManifestError::InvalidMajor] is returned.
+    /// If major version does not equal 2, and the [default](crate::VersionPolicy::default)
+    /// version policy rejects it, the error [`InvalidMajor`][crate::ManifestError::InvalidMajor]
+    /// is returned.
     ///
-    /// If minor version does not equal 0, and the feature
-    /// [`version_error`](index.html#feature-version_error) is enabled, the error
-    /// [`InvalidMinor`][crate::ManifestError::InvalidMinor] is returned.
+    /// If minor version does not equal 0, and the [default](crate::VersionPolicy::default)
+    /// version policy rejects it, the error [`InvalidMinor`][crate::ManifestError::InvalidMinor]
+    /// is returned.
     ///
     /// If [`offset`](Header::offset) is smaller or larger than the file, the error
     /// [`InvalidOffset`][crate::ManifestError::InvalidOffset] is returned.
@@ -76,16 +124,129 @@ impl Header {
     /// error [`CompressedSizeTooLarge`][crate::ManifestError::CompressedSizeTooLarge] is
     /// returned.
     pub fn from_reader<R: Read + Seek>(mut reader: R) -> Result<Self> {
-        debug!("Attempting to convert \"reader.bytes().count()\" into \"u32\".");
-        let size: u32 = reader.borrow_mut().bytes().count().try_into()?;
-        debug!("Successfully converted \"reader.bytes().count()\" into \"u32\".");
+        let size = Self::stream_size(&mut reader)?;
+        Self::parse(reader, Some(size), &VersionPolicy::default())
+    }
+
+    /// Parses the header from a reader positioned at the start of a file of known size.
+    ///
+    /// This works the same as [`from_reader`](Header::from_reader), except that the reader does
+    /// not need to implement [`Seek`], which makes it possible to parse headers from pipes,
+    /// HTTP bodies, etc. where the total size is known up front.
+    ///
+    /// After parsing, the reader is positioned right after the header (28 bytes from the start).
+    ///
+    /// # Errors
+    ///
+    /// See [`from_reader`](Header::from_reader), excluding the
+    /// [`SeekError`][crate::ManifestError::SeekError].
+    pub fn from_reader_with_size<R: Read>(reader: R, size: u64) -> Result<Self> {
+        Self::parse(reader, Some(size), &VersionPolicy::default())
+    }
+
+    /// Parses the header from a reader positioned at the start of a file of unknown size.
+    ///
+    /// This works the same as [`from_reader_with_size`](Header::from_reader_with_size), except
+    /// that [`offset`](Header::offset) and [`compressed_size`](Header::compressed_size) can't
+    /// be checked against the size of the file. Only the lower bound of the offset is checked.
+    ///
+    /// After parsing, the reader is positioned right after the header (28 bytes from the start).
+    ///
+    /// # Errors
+    ///
+    /// See [`from_reader_with_size`](Header::from_reader_with_size).
+    pub fn from_stream<R: Read>(reader: R) -> Result<Self> {
+        Self::parse(reader, None, &VersionPolicy::default())
+    }
+
+    /// Checks the [major](Header::major) and [minor](Header::minor) version against the provided
+    /// [`VersionPolicy`][crate::VersionPolicy].
+    ///
+    /// This is done automatically when parsing the header, however it can be used to check an
+    /// already parsed header against a different policy.
+    ///
+    /// # Errors
+    ///
+    /// If the policy rejects the major version, the error
+    /// [`InvalidMajor`][crate::ManifestError::InvalidMajor] is returned.
+    ///
+    /// If the policy rejects the minor version, the error
+    /// [`InvalidMinor`][crate::ManifestError::InvalidMinor] is returned.
+    pub fn check_version(&self, policy: &VersionPolicy) -> Result<()> {
+        policy.check(self.major, self.minor)
+    }
+
+    /// Returns the [manifest id](Header::manifest_id) as 16 uppercase hex digits, which is how
+    /// Riot displays it (e.g. in the manifest file names).
+    #[must_use]
+    pub fn manifest_id_hex(&self) -> String {
+        format!("{:016X}", self.manifest_id)
+    }
+
+    /// Writes the header in the same format it is [parsed](Header::from_reader) from.
+    ///
+    /// All of the fields are written as they are, including any unknown
+    /// [flags](Header::flags). Exactly 28 bytes are written, so the
+    /// [offset](Header::offset) of a manifest written right after the header should be 28.
+    ///
+    /// # Errors
+    ///
+    /// If writing to io stream fails, the error [`IoError`][crate::ManifestError::IoError] is
+    /// returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rman::Result;
+    /// use std::io::Cursor;
+    ///
+    /// use rman::{Header, RiotManifest};
+    ///
+    /// # fn main() -> Result<()> {
+    /// let path = "file.manifest";
+    ///   # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    /// let bytes = std::fs::read(path)?;
+    /// let header = Header::from_reader(Cursor::new(&bytes))?;
+    ///
+    /// let mut written = Vec::new();
+    /// header.write_to(&mut written)?;
+    /// assert_eq!(written, bytes[..28]);
+    ///   # Ok(())
+    /// # }
+    /// ```
+    pub fn write_to<W: Write>(&self, mut writer: W) -> Result<()> {
+        writer.write_u32::<LE>(self.magic)?;
+        writer.write_u8(self.major)?;
+        writer.write_u8(self.minor)?;
+        writer.write_u16::<LE>(self.flags.bits())?;
+        writer.write_u32::<LE>(self.offset)?;
+        writer.write_u32::<LE>(self.compressed_size)?;
+        writer.write_u64::<LE>(self.manifest_id)?;
+        writer.write_u32::<LE>(self.uncompressed_size)?;
+        Ok(())
+    }
+
+    /// Returns the size of the stream, and rewinds it to the start.
+    pub(crate) fn stream_size<R: Seek>(mut reader: R) -> Result<u64> {
+        let size = match reader.seek(SeekFrom::End(0)) {
+            Ok(size) => size,
+            Err(error) => return Err(ManifestError::SeekError(error)),
+        };
 
         debug!("The file is {size} bytes in size");
 
         if let Err(error) = reader.rewind() {
             return Err(ManifestError::SeekError(error));
-        };
+        }
 
+        Ok(size)
+    }
+
+    pub(crate) fn parse<R: Read>(
+        mut reader: R,
+        size: Option<u64>,
+        policy: &VersionPolicy,
+    ) -> Result<Self> {
         let magic = reader.read_u32::<LE>()?;
 
         // N A M R (RMAN bacwards because I am reading this as an u32, instead
@@ -95,36 +256,26 @@ impl Header {
         }
 
         let major = reader.read_u8()?;
-        if major != 2 {
-            warn!("Invalid major version. Parsing the manfiset may not work.");
-            info!("If you want the crate to throw an error instead, you can enable the \"version_error\" feature");
-            #[cfg(feature = "version_error")]
-            return Err(ManifestError::InvalidMajor(major));
-        }
-
         let minor = reader.read_u8()?;
-        if major == 2 && minor != 0 {
-            info!("Invalid minor version. Parsing the manfiset will probably still work.");
-            info!("If you want the crate to throw an error instead, you can enable the \"version_error\" feature");
-            #[cfg(feature = "version_error")]
-            return Err(ManifestError::InvalidMinor(minor));
-        }
+        policy.check(major, minor)?;
 
-        let flags = reader.read_u16::<LE>()?;
+        let flags = HeaderFlags::from_bits_retain(reader.read_u16::<LE>()?);
         let offset = reader.read_u32::<LE>()?;
 
-        if offset < 28 || offset >= size {
+        if offset < 28 || size.is_some_and(|size| u64::from(offset) >= size) {
             return Err(ManifestError::InvalidOffset(offset));
         }
 
         let compressed_size = reader.read_u32::<LE>()?;
-        if compressed_size > size - 28 {
-            return Err(ManifestError::CompressedSizeTooLarge(compressed_size));
-        }
-        if compressed_size + offset > size {
-            return Err(ManifestError::CompressedSizeTooLarge(
-                compressed_size + offset,
-            ));
+        if let Some(size) = size {
+            if u64::from(compressed_size) > size - 28 {
+                return Err(ManifestError::CompressedSizeTooLarge(compressed_size));
+            }
+            if u64::from(compressed_size) + u64::from(offset) > size {
+                return Err(ManifestError::CompressedSizeTooLarge(
+                    compressed_size.saturating_add(offset),
+                ));
+            }
         }
 
         let manifest_id = reader.read_u64::<LE>()?;
@@ -179,7 +330,7 @@ mod tests {
         let mut cursor = Cursor::new(helpers::VALID_HEADER);
         if let Err(error) = Header::from_reader(&mut cursor) {
             panic!("there was an error when parsing header, header: {error:?}");
-        };
+        }
     }
 
     #[test]
@@ -190,7 +341,11 @@ mod tests {
         assert_eq!(header.magic, 0x4E414D52, "magic bytes did not match");
         assert_eq!(header.major, 2, "major version did not match");
         assert_eq!(header.minor, 0, "minor version did not match");
-        assert_eq!(header.flags, 512, "flags did not match");
+        assert_eq!(
+            header.flags,
+            HeaderFlags::UNKNOWN_BIT_9,
+            "flags did not match"
+        );
         assert_eq!(header.offset, 28, "offset did not match");
         assert_eq!(header.compressed_size, 0, "compressed size did not match");
         assert_eq!(
@@ -203,6 +358,22 @@ mod tests {
         );
     }
 
+    #[test]
+    fn should_write_same_bytes_when_parsed() {
+        let mut bytes = helpers::VALID_HEADER;
+        // unknown flag bits should be preserved
+        bytes[6] = 0x01;
+
+        let header = Header::from_reader(&mut Cursor::new(bytes)).unwrap();
+        assert_eq!(header.flags.bits(), 0x0201, "flags did not match");
+        assert!(header.flags.contains(HeaderFlags::UNKNOWN_BIT_9));
+        assert_eq!(header.manifest_id_hex(), "2B9C4AABEFC317D2");
+
+        let mut written = Vec::new();
+        header.write_to(&mut written).unwrap();
+        assert_eq!(written, bytes[..28], "written header did not match");
+    }
+
     #[test]
     fn should_throw_correct_errors_when_eof() {
         // EOF when reading magic bytes
@@ -211,7 +382,7 @@ mod tests {
         match error {
             crate::error::ManifestError::IoError(_) => (),
             _ => panic!("invalid ManifestError error when eof"),
-        };
+        }
 
         // EOF when reading major
         let error = Header::from_reader(&mut Cursor::new(helpers::VALID_HEADER[..4].to_owned()))
@@ -219,7 +390,7 @@ mod tests {
         match error {
             crate::error::ManifestError::IoError(_) => (),
             _ => panic!("invalid ManifestError error when eof"),
-        };
+        }
 
         // EOF when reading minor
         let error = Header::from_reader(&mut Cursor::new(helpers::VALID_HEADER[..5].to_owned()))
@@ -227,7 +398,7 @@ mod tests {
         match error {
             crate::error::ManifestError::IoError(_) => (),
             _ => panic!("invalid ManifestError error when eof"),
-        };
+        }
 
         // EOF when reading flags
         let error = Header::from_reader(&mut Cursor::new(helpers::VALID_HEADER[..7].to_owned()))
@@ -235,7 +406,7 @@ mod tests {
         match error {
             crate::error::ManifestError::IoError(_) => (),
             _ => panic!("invalid ManifestError error when eof"),
-        };
+        }
 
         // EOF when reading offset
         let error = Header::from_reader(&mut Cursor::new(helpers::VALID_HEADER[..11].to_owned()))
@@ -243,7 +414,7 @@ mod tests {
         match error {
             crate::error::ManifestError::IoError(_) => (),
             _ => panic!("invalid ManifestError error when eof"),
-        };
+        }
 
         // it should be impossible for reading to fail at this point, because
         // offset must be greater than 28 and and less then file size, which
@@ -380,4 +551,103 @@ mod tests {
 
         helpers::assert_error!(buf, CompressedSizeTooLarge);
     }
+
+    #[test]
+    fn should_parse_when_valid_header_and_known_size() {
+        let size = helpers::VALID_HEADER.len() as u64;
+        let header = Header::from_reader_with_size(&helpers::VALID_HEADER[..], size).unwrap();
+        let expected = Header::from_reader(Cursor::new(helpers::VALID_HEADER)).unwrap();
+
+        assert_eq!(header, expected, "headers did not match");
+    }
+
+    #[test]
+    fn should_error_when_known_size_too_small() {
+        let Err(error) = Header::from_reader_with_size(&helpers::VALID_HEADER[..], 28) else {
+            panic!("did not throw an error");
+        };
+        let crate::error::ManifestError::InvalidOffset(..) = error else {
+            panic!("some other error was thrown");
+        };
+    }
+
+    #[test]
+    fn should_skip_size_checks_when_size_unknown() {
+        let buf = [
+            &helpers::VALID_HEADER[..12],
+            &u32::MAX.to_le_bytes(),
+            &helpers::VALID_HEADER[16..],
+        ]
+        .concat();
+
+        let header = Header::from_stream(&buf[..]).unwrap();
+        assert_eq!(
+            header.compressed_size,
+            u32::MAX,
+            "compressed size did not match"
+        );
+
+        let buf = [
+            &helpers::VALID_HEADER[..8],
+            &0u32.to_le_bytes(),
+            &helpers::VALID_HEADER[12..],
+        ]
+        .concat();
+
+        let Err(error) = Header::from_stream(&buf[..]) else {
+            panic!("did not throw an error");
+        };
+        let crate::error::ManifestError::InvalidOffset(..) = error else {
+            panic!("some other error was thrown");
+        };
+    }
+
+    #[test]
+    fn should_follow_version_policy_when_unknown_version() {
+        let buf = [
+            &helpers::VALID_HEADER[..4],
+            &[0x03, 0x01],
+            &helpers::VALID_HEADER[6..],
+        ]
+        .concat();
+
+        assert!(Header::parse(&buf[..], None, &VersionPolicy::Accept).is_ok());
+        assert!(Header::parse(&buf[..], None, &VersionPolicy::Warn).is_ok());
+
+        let Err(error) = Header::parse(&buf[..], None, &VersionPolicy::Reject) else {
+            panic!("did not throw an error");
+        };
+        let crate::error::ManifestError::InvalidMajor(3) = error else {
+            panic!("some other error was thrown");
+        };
+    }
+
+    #[test]
+    fn should_call_predicate_when_custom_version_policy() {
+        let policy = VersionPolicy::custom(|major, minor| major == 2 && minor <= 1);
+
+        let header = Header::from_stream(&helpers::VALID_HEADER[..]).unwrap();
+        assert!(header.check_version(&policy).is_ok());
+
+        let buf = [
+            &helpers::VALID_HEADER[..5],
+            &[0x01],
+            &helpers::VALID_HEADER[6..],
+        ]
+        .concat();
+        assert!(Header::parse(&buf[..], None, &policy).is_ok());
+
+        let buf = [
+            &helpers::VALID_HEADER[..5],
+            &[0x02],
+            &helpers::VALID_HEADER[6..],
+        ]
+        .concat();
+        let Err(error) = Header::parse(&buf[..], None, &policy) else {
+            panic!("did not throw an error");
+        };
+        let crate::error::ManifestError::InvalidMinor(2) = error else {
+            panic!("some other error was thrown");
+        };
+    }
 }
@@ -3,11 +3,23 @@ use std::collections::HashMap;
 use crate::entries::{
     BundleEntry, ChunkingParamEntry, DirectoryEntry, FileEntry, KeyEntry, TagEntry,
 };
-use crate::generated::rman::root_as_manifest_with_opts;
-use crate::File;
-use crate::Result;
+use crate::generated::rman::{
+    finish_manifest_buffer, root_as_manifest_with_opts, Bundle, BundleArgs, Chunk, ChunkArgs,
+    ChunkingParam, ChunkingParamArgs, Directory, DirectoryArgs, File as FileTable, FileArgs, Key,
+    KeyArgs, Manifest, ManifestArgs, Tag, TagArgs,
+};
+use flatbuffers::FlatBufferBuilder;
+use log::warn;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+
+use crate::file::DirectoryPaths;
+use crate::{
+    ChunkId, Diagnostic, DirectoryId, File, FileChunk, ManifestError, ParseOptions, Result,
+};
 
 /// Stores all of the flatbuffer data, as well as the parsed files.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct ManifestData {
     /// Vector of [bundle entries][crate::entries::BundleEntry].
@@ -24,8 +36,15 @@ pub struct ManifestData {
     pub chunking_param_entries: Vec<ChunkingParamEntry>,
     /// Vector of [files][crate::File].
     pub files: Vec<File>,
+    /// Warnings about [file entries][crate::entries::FileEntry] that could not be resolved.
+    ///
+    /// Only filled when parsing with
+    /// [`ParseOptions::with_lenient`][crate::ParseOptions::with_lenient], in which case such
+    /// files are left out of [`files`](ManifestData::files), otherwise parsing fails instead.
+    pub warnings: Vec<Diagnostic>,
 }
 
+#[cfg(not(feature = "rayon"))]
 macro_rules! map_vector {
     ($manifest: ident, $name: ident, $entry: ident) => {
         $manifest
@@ -37,6 +56,17 @@ macro_rules! map_vector {
     };
 }
 
+#[cfg(feature = "rayon")]
+macro_rules! map_vector {
+    ($manifest: ident, $name: ident, $entry: ident) => {{
+        let vector = $manifest.$name().unwrap_or_default();
+        (0..vector.len())
+            .into_par_iter()
+            .map(|i| $entry::from(vector.get(i)))
+            .collect()
+    }};
+}
+
 impl ManifestData {
     /// Main flatbuffer parser method.
     ///
@@ -47,24 +77,39 @@ impl ManifestData {
     /// contents and provides permanent objects so that the buffer can be discarded. Not to
     /// mention the fact that it's still plenty fast. :^)
     ///
+    /// Files are only resolved if [`ParseOptions::resolve_files`][crate::ParseOptions::resolve_files]
+    /// is `true` (which is the default), otherwise [`files`](ManifestData::files) is left empty.
+    ///
     /// # Errors
     ///
     /// If verifying the flatbuffer fails, the error
     /// [`FlatbufferError`][crate::ManifestError::FlatbufferError] is returned.
     ///
-    /// If parsing the [`File`][crate::File] fails, it propagates an error from
-    /// [`File::parse`][crate::File::parse].
-    pub fn parse(
-        bytes: &[u8],
-        flatbuffer_verifier_options: Option<&flatbuffers::VerifierOptions>,
-    ) -> Result<Self> {
-        let opts = flatbuffers::VerifierOptions {
-            max_tables: 10_000_000,
-            ..Default::default()
-        };
-        let manifest =
-            root_as_manifest_with_opts(flatbuffer_verifier_options.unwrap_or(&opts), bytes)?;
+    /// If the amount of files or directories exceeds the limits set in [`ParseOptions`], the
+    /// error [`FileCountLimitExceeded`][crate::ManifestError::FileCountLimitExceeded] or
+    /// [`DirectoryCountLimitExceeded`][crate::ManifestError::DirectoryCountLimitExceeded] is
+    /// returned.
+    ///
+    /// If a chunk is larger than the configured limit, the error
+    /// [`ChunkSizeLimitExceeded`][crate::ManifestError::ChunkSizeLimitExceeded] is returned.
+    ///
+    /// If resolving the files fails, it propagates an error from
+    /// [`ManifestData::resolve_files`].
+    pub fn parse(bytes: &[u8], options: Option<&ParseOptions>) -> Result<Self> {
+        let default_options = ParseOptions::default();
+        let options = options.unwrap_or(&default_options);
+
+        let manifest = root_as_manifest_with_opts(&options.verifier_options(), bytes)?;
+
+        options.check_counts(
+            manifest.files().map_or(0, |f| f.len()),
+            manifest.directories().map_or(0, |d| d.len()),
+        )?;
+
+        Self::from_manifest(manifest, options)
+    }
 
+    pub(crate) fn from_manifest(manifest: Manifest<'_>, options: &ParseOptions) -> Result<Self> {
         let bundle_entries: Vec<_> = map_vector!(manifest, bundles, BundleEntry);
         let directory_entries: Vec<_> = map_vector!(manifest, directories, DirectoryEntry);
         let file_entries: Vec<_> = map_vector!(manifest, files, FileEntry);
@@ -72,54 +117,391 @@ impl ManifestData {
         let tag_entries: Vec<_> = map_vector!(manifest, tags, TagEntry);
         let chunking_param_entries = map_vector!(manifest, chunking_params, ChunkingParamEntry);
 
-        let mapped_tags = Self::map_tags(&tag_entries);
-        let mapped_directories = Self::map_directories(&directory_entries);
-        let mapped_chunks = Self::map_chunks(&bundle_entries);
-
-        let files = file_entries
-            .iter()
-            .map(|f| File::parse(f, &mapped_tags, &mapped_directories, &mapped_chunks))
-            .collect::<Result<Vec<File>>>()?;
-
-        Ok(Self {
+        let mut data = Self {
             bundle_entries,
             directory_entries,
             file_entries,
             key_entries,
             tag_entries,
             chunking_param_entries,
-            files,
-        })
+            files: Vec::new(),
+            warnings: Vec::new(),
+        };
+
+        if options.resolve_files() {
+            (data.files, data.warnings) = data.resolve_files_with_warnings(Some(options))?;
+        } else {
+            for chunk in data.bundle_entries.iter().flat_map(|b| &b.chunks) {
+                options.check_chunk_size(chunk)?;
+            }
+        }
+
+        Ok(data)
+    }
+
+    /// Resolves all of the [file entries][crate::entries::FileEntry] into [files][crate::File].
+    ///
+    /// This is done automatically when parsing, unless
+    /// [`ParseOptions::with_resolve_files`][crate::ParseOptions::with_resolve_files] was set to
+    /// `false`.
+    ///
+    /// The limits from `options` are applied the same as when parsing. If `options` is
+    /// [`None`], [`ParseOptions::default()`] is used.
+    ///
+    /// # Errors
+    ///
+    /// If the amount of files or directories exceeds the configured limits, the error
+    /// [`FileCountLimitExceeded`][crate::ManifestError::FileCountLimitExceeded] or
+    /// [`DirectoryCountLimitExceeded`][crate::ManifestError::DirectoryCountLimitExceeded] is
+    /// returned.
+    ///
+    /// If a chunk is larger than the configured limit, the error
+    /// [`ChunkSizeLimitExceeded`][crate::ManifestError::ChunkSizeLimitExceeded] is returned.
+    ///
+    /// If a resolved path is longer than the configured limit, the error
+    /// [`PathLengthLimitExceeded`][crate::ManifestError::PathLengthLimitExceeded] is returned.
+    ///
+    /// If parsing the [`File`][crate::File] fails, it propagates an error from
+    /// [`File::parse`][crate::File::parse].
+    pub fn resolve_files(&self, options: Option<&ParseOptions>) -> Result<Vec<File>> {
+        self.resolve_files_with_warnings(options)
+            .map(|(files, _)| files)
+    }
+
+    /// Resolves all of the [file entries][crate::entries::FileEntry] into [files][crate::File]
+    /// like [`resolve_files`](ManifestData::resolve_files), while also returning warnings about
+    /// the file entries that could not be resolved.
+    ///
+    /// Warnings can only be produced when parsing with
+    /// [`ParseOptions::with_lenient`][crate::ParseOptions::with_lenient].
+    ///
+    /// # Errors
+    ///
+    /// See [`resolve_files`](ManifestData::resolve_files).
+    pub fn resolve_files_with_warnings(
+        &self,
+        options: Option<&ParseOptions>,
+    ) -> Result<(Vec<File>, Vec<Diagnostic>)> {
+        let default_options = ParseOptions::default();
+        let options = options.unwrap_or(&default_options);
+
+        options.check_counts(self.file_entries.len(), self.directory_entries.len())?;
+        for chunk in self.bundle_entries.iter().flat_map(|b| &b.chunks) {
+            options.check_chunk_size(chunk)?;
+        }
+
+        let mapped_tags = Self::map_tags(&self.tag_entries);
+        let mapped_directories = Self::map_directories(&self.directory_entries);
+        let mapped_chunks = Self::map_chunks(&self.bundle_entries)?;
+
+        let mut directory_paths = DirectoryPaths::new(&mapped_directories, options);
+        for file_entry in &self.file_entries {
+            // errors are reported for each file when it is parsed
+            let _ = directory_paths.resolve(file_entry.directory_id);
+        }
+
+        let results = map_in_order(&self.file_entries, |file_entry| {
+            let result = File::parse_with_directory_paths(
+                file_entry,
+                &mapped_tags,
+                &directory_paths,
+                &mapped_chunks,
+                options,
+            );
+            match result {
+                Ok(file) => Ok(Some(file)),
+                Err(ManifestError::FileParseError(_) | ManifestError::DirectoryCycle(_))
+                    if options.lenient() =>
+                {
+                    warn!(
+                        "Skipping file \"{}\", since it could not be resolved.",
+                        file_entry.name
+                    );
+                    Ok(None)
+                }
+                Err(error) => Err(error),
+            }
+        });
+
+        let mut files = Vec::with_capacity(self.file_entries.len());
+        let mut warnings = Vec::new();
+
+        for (file_entry, result) in self.file_entries.iter().zip(results) {
+            match result? {
+                Some(file) => files.push(file),
+                None => warnings.extend(Self::file_warnings(
+                    file_entry,
+                    &directory_paths,
+                    &mapped_chunks,
+                )),
+            }
+        }
+
+        Ok((files, warnings))
+    }
+
+    fn file_warnings(
+        file_entry: &FileEntry,
+        directory_paths: &DirectoryPaths<'_>,
+        chunk_entries: &HashMap<ChunkId, FileChunk>,
+    ) -> Vec<Diagnostic> {
+        let mut warnings: Vec<_> = file_entry
+            .chunk_ids
+            .iter()
+            .filter(|chunk_id| !chunk_entries.contains_key(chunk_id))
+            .map(|chunk_id| Diagnostic::UnknownChunk {
+                file_id: file_entry.id,
+                chunk_id: *chunk_id,
+            })
+            .collect();
+
+        match directory_paths.get(file_entry.directory_id) {
+            Ok(_) => {}
+            Err(ManifestError::DirectoryCycle(directory_id)) => {
+                warnings.push(Diagnostic::DirectoryCycle {
+                    file_id: file_entry.id,
+                    directory_id,
+                });
+            }
+            Err(_) => warnings.push(Diagnostic::UnknownDirectory {
+                file_id: file_entry.id,
+                directory_id: file_entry.directory_id,
+            }),
+        }
+
+        warnings
     }
 
     fn map_tags(tag_entries: &[TagEntry]) -> HashMap<u8, String> {
         tag_entries.iter().map(|l| (l.id, l.name.clone())).collect()
     }
 
-    fn map_directories(directory_entries: &[DirectoryEntry]) -> HashMap<i64, (String, i64)> {
+    fn map_directories(
+        directory_entries: &[DirectoryEntry],
+    ) -> HashMap<DirectoryId, (String, DirectoryId)> {
         directory_entries
             .iter()
             .map(|d| (d.id, (d.name.clone(), d.parent_id)))
             .collect()
     }
 
-    fn map_chunks(bundle_entries: &[BundleEntry]) -> HashMap<i64, (i64, u32, u32, u32)> {
-        bundle_entries
+    /// Returns the [chunking param entry][crate::entries::ChunkingParamEntry] of a resolved
+    /// [`File`][crate::File], or [`None`] if its
+    /// [`chunking_param_id`](crate::File::chunking_param_id) is out of bounds.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rman::Result;
+    /// use rman::entries::ChunkHashType;
+    /// use rman::RiotManifest;
+    ///
+    /// # fn main() -> Result<()> {
+    /// let path = "file.manifest";
+    ///   # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    /// let manifest = RiotManifest::from_path(path, None)?;
+    ///
+    /// for file in &manifest.data.files {
+    ///     let hash_type = manifest.data.chunking_param(file).map(|p| p.hash_type());
+    ///     match hash_type {
+    ///         Some(ChunkHashType::Sha256) => println!("{} uses SHA-256", file.path),
+    ///         Some(ChunkHashType::RiotHkdf) => println!("{} uses HKDF", file.path),
+    ///         _ => println!("{} uses {hash_type:?}", file.path),
+    ///     }
+    /// }
+    ///   # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn chunking_param(&self, file: &File) -> Option<&ChunkingParamEntry> {
+        self.chunking_param_entries
+            .get(usize::from(file.chunking_param_id))
+    }
+
+    /// Serializes all of the entries back into a flatbuffer binary.
+    ///
+    /// This is the inverse of [`ManifestData::parse`], so the result can be compressed and written
+    /// into a `.manifest` file (see [`RiotManifest::to_bytes`][crate::RiotManifest::to_bytes]).
+    /// Only the entries are written, [`files`](ManifestData::files) and
+    /// [`warnings`](ManifestData::warnings) are ignored, since they are derived from the entries.
+    ///
+    /// The output is not guaranteed to be byte-for-byte identical to the original binary, but
+    /// it parses into the same entries.
+    #[must_use]
+    pub fn to_flatbuffer(&self) -> Vec<u8> {
+        let mut builder = FlatBufferBuilder::new();
+
+        let bundles: Vec<_> = self
+            .bundle_entries
             .iter()
-            .flat_map(|b| {
-                b.chunks.iter().scan(0, move |offset, c| {
-                    *offset += c.compressed_size;
-                    Some((
-                        c.id,
-                        (
-                            b.id,
-                            *offset - c.compressed_size,
-                            c.uncompressed_size,
-                            c.compressed_size,
-                        ),
-                    ))
-                })
+            .map(|bundle| {
+                let chunks: Vec<_> = bundle
+                    .chunks
+                    .iter()
+                    .map(|chunk| {
+                        let args = ChunkArgs {
+                            id: chunk.id.get(),
+                            compressed_size: chunk.compressed_size,
+                            uncompressed_size: chunk.uncompressed_size,
+                        };
+                        Chunk::create(&mut builder, &args)
+                    })
+                    .collect();
+                let args = BundleArgs {
+                    id: bundle.id.get(),
+                    chunks: Some(builder.create_vector(&chunks)),
+                };
+                Bundle::create(&mut builder, &args)
             })
-            .collect()
+            .collect();
+        let bundles = builder.create_vector(&bundles);
+
+        let tags: Vec<_> = self
+            .tag_entries
+            .iter()
+            .map(|tag| {
+                let args = TagArgs {
+                    id: tag.id,
+                    name: Some(builder.create_string(&tag.name)),
+                };
+                Tag::create(&mut builder, &args)
+            })
+            .collect();
+        let tags = builder.create_vector(&tags);
+
+        let files = Self::build_files(&mut builder, &self.file_entries);
+
+        let directories: Vec<_> = self
+            .directory_entries
+            .iter()
+            .map(|directory| {
+                let args = DirectoryArgs {
+                    id: directory.id.get(),
+                    parent_id: directory.parent_id.get(),
+                    name: Some(builder.create_string(&directory.name)),
+                };
+                Directory::create(&mut builder, &args)
+            })
+            .collect();
+        let directories = builder.create_vector(&directories);
+
+        let keys: Vec<_> = self
+            .key_entries
+            .iter()
+            .map(|key| {
+                let args = KeyArgs {
+                    unk0: key.unk0,
+                    unk1: key.unk1,
+                };
+                Key::create(&mut builder, &args)
+            })
+            .collect();
+        let keys = builder.create_vector(&keys);
+
+        let chunking_params: Vec<_> = self
+            .chunking_param_entries
+            .iter()
+            .map(|param| {
+                let args = ChunkingParamArgs {
+                    unk0: param.unk0,
+                    chunking_version: param.chunking_version,
+                    min_chunk_size: param.min_chunk_size,
+                    chunk_size: param.chunk_size,
+                    max_chunk_size: param.max_chunk_size,
+                };
+                ChunkingParam::create(&mut builder, &args)
+            })
+            .collect();
+        let chunking_params = builder.create_vector(&chunking_params);
+
+        let args = ManifestArgs {
+            bundles: Some(bundles),
+            tags: Some(tags),
+            files: Some(files),
+            directories: Some(directories),
+            keys: Some(keys),
+            chunking_params: Some(chunking_params),
+        };
+        let manifest = Manifest::create(&mut builder, &args);
+        finish_manifest_buffer(&mut builder, manifest);
+
+        builder.finished_data().to_vec()
+    }
+
+    fn build_files<'a>(
+        builder: &mut FlatBufferBuilder<'a>,
+        file_entries: &[FileEntry],
+    ) -> flatbuffers::WIPOffset<flatbuffers::Vector<'a, flatbuffers::ForwardsUOffset<FileTable<'a>>>>
+    {
+        let files: Vec<_> = file_entries
+            .iter()
+            .map(|file| {
+                let args = FileArgs {
+                    id: file.id.get(),
+                    directory_id: file.directory_id.get(),
+                    size_: file.size,
+                    name: Some(builder.create_string(&file.name)),
+                    tag_bitmask: file.tag_bitmask,
+                    unk5: file.unk5,
+                    unk6: file.unk6,
+                    chunk_ids: Some(
+                        builder.create_vector_from_iter(file.chunk_ids.iter().map(|id| id.get())),
+                    ),
+                    unk8: file.unk8,
+                    symlink: Some(builder.create_string(&file.symlink)),
+                    unk10: file.unk10,
+                    chunking_param_id: file.chunking_param_id,
+                    permissions: file.permissions.bits(),
+                };
+                FileTable::create(builder, &args)
+            })
+            .collect();
+        builder.create_vector(&files)
     }
+
+    fn map_chunks(bundle_entries: &[BundleEntry]) -> Result<HashMap<ChunkId, FileChunk>> {
+        let chunks = map_in_order(bundle_entries, |b| {
+            let mut offset: u32 = 0;
+            let mut chunks = Vec::with_capacity(b.chunks.len());
+            for c in &b.chunks {
+                let Some(end) = offset.checked_add(c.compressed_size) else {
+                    return Err(ManifestError::InvalidChunkRange(c.id));
+                };
+                chunks.push((
+                    c.id,
+                    FileChunk {
+                        id: c.id,
+                        bundle_id: b.id,
+                        offset,
+                        uncompressed_size: c.uncompressed_size,
+                        compressed_size: c.compressed_size,
+                    },
+                ));
+                offset = end;
+            }
+            Ok(chunks)
+        });
+
+        let mut mapped = HashMap::new();
+        for chunks in chunks {
+            mapped.extend(chunks?);
+        }
+        Ok(mapped)
+    }
+}
+
+/// Maps all of the items, keeping them in the same order.
+///
+/// If the feature `rayon` is enabled, the items are mapped in parallel.
+fn map_in_order<T, R, F>(items: &[T], f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    #[cfg(feature = "rayon")]
+    return items.par_iter().map(f).collect();
+
+    #[cfg(not(feature = "rayon"))]
+    return items.iter().map(f).collect();
 }
@@ -0,0 +1,437 @@
+use std::fmt;
+use std::sync::Arc;
+
+use log::{debug, info, warn};
+
+use crate::entries::ChunkEntry;
+use crate::{Header, ManifestError, Result};
+
+/// Default limit for the [compressed size](crate::Header::compressed_size) (256 MiB).
+const DEFAULT_MAX_COMPRESSED_SIZE: u32 = 256 * 1024 * 1024;
+/// Default limit for the [uncompressed size](crate::Header::uncompressed_size) (1 GiB).
+const DEFAULT_MAX_UNCOMPRESSED_SIZE: u32 = 1024 * 1024 * 1024;
+/// Default limit for the amount of file and directory entries.
+const DEFAULT_MAX_ENTRIES: usize = 1_000_000;
+/// Default limit for the length of a file path.
+const DEFAULT_MAX_PATH_LENGTH: usize = 4096;
+/// Default limit for the amount of directories a file can be nested in.
+const DEFAULT_MAX_DIRECTORY_DEPTH: usize = 256;
+/// Default limit for the compressed and uncompressed size of a chunk (64 MiB).
+///
+/// Also used by [`Downloader`][crate::Downloader].
+pub const DEFAULT_MAX_CHUNK_SIZE: u32 = 64 * 1024 * 1024;
+
+/// Decides what happens when a manifest has an unknown [major](crate::Header::major) or
+/// [minor](crate::Header::minor) version.
+///
+/// So far, the only known version is 2.0.
+///
+/// # Examples
+///
+/// ```rust
+/// use rman::{ParseOptions, VersionPolicy};
+///
+/// // only accept 2.x manifests
+/// let policy = VersionPolicy::custom(|major, _minor| major == 2);
+/// let options = ParseOptions::new().with_version_policy(policy);
+///   # let _ = options;
+/// ```
+#[derive(Clone)]
+pub enum VersionPolicy {
+    /// Unknown versions are accepted silently.
+    Accept,
+    /// Unknown versions are accepted, but logged using the [log crate][log].
+    Warn,
+    /// Unknown versions are rejected with the error
+    /// [`InvalidMajor`][crate::ManifestError::InvalidMajor] or
+    /// [`InvalidMinor`][crate::ManifestError::InvalidMinor].
+    Reject,
+    /// The predicate is called with the major and minor version, and the version is accepted if
+    /// it returns `true`. Otherwise it is rejected the same as with [`VersionPolicy::Reject`].
+    ///
+    /// The predicate is called for every version, including the known one.
+    Custom(Arc<dyn Fn(u8, u8) -> bool + Send + Sync>),
+}
+
+impl VersionPolicy {
+    /// Creates a [`VersionPolicy::Custom`] from the provided predicate.
+    pub fn custom<F: Fn(u8, u8) -> bool + Send + Sync + 'static>(predicate: F) -> Self {
+        Self::Custom(Arc::new(predicate))
+    }
+
+    pub(crate) fn check(&self, major: u8, minor: u8) -> Result<()> {
+        let rejected_error = || {
+            if major == 2 {
+                ManifestError::InvalidMinor(minor)
+            } else {
+                ManifestError::InvalidMajor(major)
+            }
+        };
+
+        if let Self::Custom(predicate) = self {
+            if predicate(major, minor) {
+                return Ok(());
+            }
+            return Err(rejected_error());
+        }
+
+        if major == 2 && minor == 0 {
+            return Ok(());
+        }
+
+        match self {
+            Self::Accept => {
+                debug!("Unknown manifest version {major}.{minor}, accepting it.");
+                Ok(())
+            }
+            Self::Warn => {
+                if major == 2 {
+                    info!("Invalid minor version. Parsing the manifest will probably still work.");
+                } else {
+                    warn!("Invalid major version. Parsing the manifest may not work.");
+                }
+                info!("If you want the crate to throw an error instead, you can use \"VersionPolicy::Reject\"");
+                Ok(())
+            }
+            Self::Reject | Self::Custom(_) => Err(rejected_error()),
+        }
+    }
+}
+
+impl Default for VersionPolicy {
+    /// Returns [`VersionPolicy::Reject`] if the feature
+    /// [`version_error`](index.html#feature-version_error) is enabled, otherwise
+    /// [`VersionPolicy::Warn`].
+    fn default() -> Self {
+        if cfg!(feature = "version_error") {
+            Self::Reject
+        } else {
+            Self::Warn
+        }
+    }
+}
+
+impl fmt::Debug for VersionPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Accept => f.write_str("Accept"),
+            Self::Warn => f.write_str("Warn"),
+            Self::Reject => f.write_str("Reject"),
+            Self::Custom(_) => f.write_str("Custom(..)"),
+        }
+    }
+}
+
+/// Options that control how a manifest is parsed.
+///
+/// All of the parsing functions accept an `Option<&ParseOptions>`, where [`None`] is the same as
+/// passing [`ParseOptions::default()`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use rman::Result;
+/// use rman::{ParseOptions, RiotManifest};
+///
+/// # fn main() -> Result<()> {
+/// let path = "file.manifest";
+///   # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+/// let options = ParseOptions::new()
+///     .with_max_tables(1_000_000)
+///     .with_max_uncompressed_size(Some(64 * 1024 * 1024))
+///     .with_resolve_files(false);
+///
+/// let mut manifest = RiotManifest::from_path(path, Some(&options))?;
+/// assert!(manifest.data.files.is_empty());
+///
+/// manifest.data.files = manifest.data.resolve_files(Some(&options))?;
+/// assert_eq!(manifest.data.files.len(), 1);
+///   # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    max_depth: usize,
+    max_tables: usize,
+    max_apparent_size: usize,
+    ignore_missing_null_terminator: bool,
+    max_compressed_size: Option<u32>,
+    max_uncompressed_size: Option<u32>,
+    max_files: Option<usize>,
+    max_directories: Option<usize>,
+    max_path_length: Option<usize>,
+    max_directory_depth: Option<usize>,
+    max_chunk_size: Option<u32>,
+    version_policy: VersionPolicy,
+    resolve_files: bool,
+    lenient: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        let verifier_options = flatbuffers::VerifierOptions::default();
+
+        Self {
+            max_depth: verifier_options.max_depth,
+            max_tables: 10_000_000,
+            max_apparent_size: verifier_options.max_apparent_size,
+            ignore_missing_null_terminator: verifier_options.ignore_missing_null_terminator,
+            max_compressed_size: Some(DEFAULT_MAX_COMPRESSED_SIZE),
+            max_uncompressed_size: Some(DEFAULT_MAX_UNCOMPRESSED_SIZE),
+            max_files: Some(DEFAULT_MAX_ENTRIES),
+            max_directories: Some(DEFAULT_MAX_ENTRIES),
+            max_path_length: Some(DEFAULT_MAX_PATH_LENGTH),
+            max_directory_depth: Some(DEFAULT_MAX_DIRECTORY_DEPTH),
+            max_chunk_size: Some(DEFAULT_MAX_CHUNK_SIZE),
+            version_policy: VersionPolicy::default(),
+            resolve_files: true,
+            lenient: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Creates the default options.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum depth of nested tables allowed in the flatbuffer.
+    ///
+    /// Defaults to 64.
+    #[must_use]
+    pub const fn with_max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// Sets the maximum number of tables allowed in the flatbuffer.
+    ///
+    /// Defaults to 10 000 000, which is enough for the largest known manifests.
+    #[must_use]
+    pub const fn with_max_tables(mut self, max_tables: usize) -> Self {
+        self.max_tables = max_tables;
+        self
+    }
+
+    /// Sets the maximum "apparent" size of the flatbuffer, if it was expanded into a tree.
+    ///
+    /// Defaults to 2 GiB.
+    #[must_use]
+    pub const fn with_max_apparent_size(mut self, max_apparent_size: usize) -> Self {
+        self.max_apparent_size = max_apparent_size;
+        self
+    }
+
+    /// Sets whether strings in the flatbuffer that are missing a null terminator are accepted.
+    ///
+    /// Defaults to `false`.
+    #[must_use]
+    pub const fn with_ignore_missing_null_terminator(mut self, ignore: bool) -> Self {
+        self.ignore_missing_null_terminator = ignore;
+        self
+    }
+
+    /// Sets the maximum allowed [compressed size](crate::Header::compressed_size).
+    ///
+    /// The size is checked before any memory is allocated for the compressed data. If [`None`],
+    /// only the checks against the size of the file are done. Defaults to 256 MiB.
+    #[must_use]
+    pub const fn with_max_compressed_size(mut self, max_compressed_size: Option<u32>) -> Self {
+        self.max_compressed_size = max_compressed_size;
+        self
+    }
+
+    /// Sets the maximum allowed [uncompressed size](crate::Header::uncompressed_size).
+    ///
+    /// The size is checked before any memory is allocated for the decompressed data. If
+    /// [`None`], the size is not limited. Defaults to 1 GiB.
+    #[must_use]
+    pub const fn with_max_uncompressed_size(mut self, max_uncompressed_size: Option<u32>) -> Self {
+        self.max_uncompressed_size = max_uncompressed_size;
+        self
+    }
+
+    /// Sets the maximum allowed amount of [file entries][crate::entries::FileEntry].
+    ///
+    /// The amount is checked before any of the entries are parsed. If [`None`], the amount is
+    /// not limited. Defaults to 1 000 000.
+    #[must_use]
+    pub const fn with_max_files(mut self, max_files: Option<usize>) -> Self {
+        self.max_files = max_files;
+        self
+    }
+
+    /// Sets the maximum allowed amount of [directory entries][crate::entries::DirectoryEntry].
+    ///
+    /// The amount is checked before any of the entries are parsed. If [`None`], the amount is
+    /// not limited. Defaults to 1 000 000.
+    #[must_use]
+    pub const fn with_max_directories(mut self, max_directories: Option<usize>) -> Self {
+        self.max_directories = max_directories;
+        self
+    }
+
+    /// Sets the maximum allowed length (in bytes) of a resolved [file path](crate::File::path).
+    ///
+    /// If [`None`], the length is not limited. Defaults to 4096.
+    #[must_use]
+    pub const fn with_max_path_length(mut self, max_path_length: Option<usize>) -> Self {
+        self.max_path_length = max_path_length;
+        self
+    }
+
+    /// Sets the maximum allowed amount of directories a [file](crate::File) can be nested in.
+    ///
+    /// Directory cycles are always detected, even without this limit (a long cycle may be
+    /// reported as exceeding the limit instead). If [`None`], the depth is not limited. Defaults
+    /// to 256.
+    #[must_use]
+    pub const fn with_max_directory_depth(mut self, max_directory_depth: Option<usize>) -> Self {
+        self.max_directory_depth = max_directory_depth;
+        self
+    }
+
+    /// Sets the maximum allowed compressed and uncompressed size of a
+    /// [chunk][crate::entries::ChunkEntry].
+    ///
+    /// Chunk sizes are later trusted when downloading, so the limit should only be raised or
+    /// removed for trusted manifests (chunks in official manifests are at most a few MiB). If
+    /// [`None`], the size is not limited. Defaults to 64 MiB.
+    #[must_use]
+    pub const fn with_max_chunk_size(mut self, max_chunk_size: Option<u32>) -> Self {
+        self.max_chunk_size = max_chunk_size;
+        self
+    }
+
+    /// Sets the [`VersionPolicy`] used when the header has an unknown version.
+    ///
+    /// Defaults to [`VersionPolicy::default()`].
+    #[must_use]
+    pub fn with_version_policy(mut self, version_policy: VersionPolicy) -> Self {
+        self.version_policy = version_policy;
+        self
+    }
+
+    /// Returns the [`VersionPolicy`] used when the header has an unknown version.
+    #[must_use]
+    pub const fn version_policy(&self) -> &VersionPolicy {
+        &self.version_policy
+    }
+
+    /// Sets whether [file entries][crate::entries::FileEntry] are resolved into
+    /// [files][crate::File] while parsing.
+    ///
+    /// If `false`, [`ManifestData::files`][crate::ManifestData::files] is left empty, and the
+    /// files can be resolved later with
+    /// [`ManifestData::resolve_files`][crate::ManifestData::resolve_files]. Defaults to `true`.
+    #[must_use]
+    pub const fn with_resolve_files(mut self, resolve_files: bool) -> Self {
+        self.resolve_files = resolve_files;
+        self
+    }
+
+    /// Returns whether file entries are resolved into files while parsing.
+    #[must_use]
+    pub const fn resolve_files(&self) -> bool {
+        self.resolve_files
+    }
+
+    /// Sets whether file entries that can't be resolved are skipped instead of failing.
+    ///
+    /// If `true`, a file entry with a missing directory (or directories that form a cycle) or
+    /// a missing chunk is left out of [`ManifestData::files`][crate::ManifestData::files], and
+    /// warnings about it are added to
+    /// [`ManifestData::warnings`][crate::ManifestData::warnings]. The rest of the manifest is
+    /// still loaded. Configured limits are always enforced. Defaults to `false`.
+    #[must_use]
+    pub const fn with_lenient(mut self, lenient: bool) -> Self {
+        self.lenient = lenient;
+        self
+    }
+
+    /// Returns whether file entries that can't be resolved are skipped instead of failing.
+    #[must_use]
+    pub const fn lenient(&self) -> bool {
+        self.lenient
+    }
+
+    pub(crate) const fn verifier_options(&self) -> flatbuffers::VerifierOptions {
+        flatbuffers::VerifierOptions {
+            max_depth: self.max_depth,
+            max_tables: self.max_tables,
+            max_apparent_size: self.max_apparent_size,
+            ignore_missing_null_terminator: self.ignore_missing_null_terminator,
+        }
+    }
+
+    pub(crate) fn check_counts(&self, files: usize, directories: usize) -> Result<()> {
+        if self.max_files.is_some_and(|max| files > max) {
+            return Err(ManifestError::FileCountLimitExceeded(files));
+        }
+        if self.max_directories.is_some_and(|max| directories > max) {
+            return Err(ManifestError::DirectoryCountLimitExceeded(directories));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_path_length(&self, length: usize) -> Result<()> {
+        if self.max_path_length.is_some_and(|max| length > max) {
+            return Err(ManifestError::PathLengthLimitExceeded(length));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn check_directory_depth(&self, depth: usize) -> Result<()> {
+        if self.max_directory_depth.is_some_and(|max| depth > max) {
+            return Err(ManifestError::DirectoryDepthLimitExceeded(depth));
+        }
+        Ok(())
+    }
+
+    pub(crate) const fn check_chunk_size(&self, chunk: &ChunkEntry) -> Result<()> {
+        check_chunk_size(
+            self.max_chunk_size,
+            chunk.compressed_size,
+            chunk.uncompressed_size,
+        )
+    }
+
+    pub(crate) fn check_header(&self, header: &Header) -> Result<()> {
+        if self
+            .max_compressed_size
+            .is_some_and(|max| header.compressed_size > max)
+        {
+            return Err(ManifestError::CompressedSizeLimitExceeded(
+                header.compressed_size,
+            ));
+        }
+        if self
+            .max_uncompressed_size
+            .is_some_and(|max| header.uncompressed_size > max)
+        {
+            return Err(ManifestError::UncompressedSizeLimitExceeded(
+                header.uncompressed_size,
+            ));
+        }
+        Ok(())
+    }
+}
+
+pub const fn check_chunk_size(
+    max_chunk_size: Option<u32>,
+    compressed_size: u32,
+    uncompressed_size: u32,
+) -> Result<()> {
+    let Some(max) = max_chunk_size else {
+        return Ok(());
+    };
+    if compressed_size > max {
+        return Err(ManifestError::ChunkSizeLimitExceeded(compressed_size));
+    }
+    if uncompressed_size > max {
+        return Err(ManifestError::ChunkSizeLimitExceeded(uncompressed_size));
+    }
+    Ok(())
+}
@@ -0,0 +1,531 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::entries::{ChunkEntry, FileEntry};
+use crate::{BundleId, ChunkId, DirectoryId, FileId, ManifestData};
+
+/// Single problem found by [`ManifestData::validate`][crate::ManifestData::validate].
+///
+/// Diagnostics don't prevent the manifest from being parsed, however they usually mean that
+/// the manifest is malformed, or that installing it would not produce the expected result.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Diagnostic {
+    /// Multiple [file entries][crate::entries::FileEntry] share the same id.
+    DuplicateFileId(FileId),
+    /// Multiple [chunk entries][crate::entries::ChunkEntry] share the same id.
+    DuplicateChunkId(ChunkId),
+    /// Multiple [bundle entries][crate::entries::BundleEntry] share the same id.
+    DuplicateBundleId(BundleId),
+    /// Multiple [directory entries][crate::entries::DirectoryEntry] share the same id.
+    DuplicateDirectoryId(DirectoryId),
+    /// A chunk is not referenced by any file.
+    UnusedChunk {
+        /// Id of the chunk.
+        chunk_id: ChunkId,
+        /// Id of the bundle the chunk belongs to.
+        bundle_id: BundleId,
+    },
+    /// A file references a chunk that does not exist.
+    UnknownChunk {
+        /// Id of the file.
+        file_id: FileId,
+        /// Id of the missing chunk.
+        chunk_id: ChunkId,
+    },
+    /// A file is in a directory that does not exist, or one of its parent directories does not
+    /// exist.
+    UnknownDirectory {
+        /// Id of the file.
+        file_id: FileId,
+        /// Id of the directory that could not be resolved.
+        directory_id: DirectoryId,
+    },
+    /// The parents of a file's directory form a cycle, so its path never reaches the root.
+    DirectoryCycle {
+        /// Id of the file.
+        file_id: FileId,
+        /// Id of a directory that is part of the cycle.
+        directory_id: DirectoryId,
+    },
+    /// The [size](crate::entries::FileEntry::size) of a file is not equal to the sum of
+    /// uncompressed sizes of its chunks.
+    SizeMismatch {
+        /// Id of the file.
+        file_id: FileId,
+        /// Size of the file entry.
+        size: u32,
+        /// Sum of uncompressed sizes of all of the file's chunks.
+        chunks_size: u64,
+    },
+    /// A bit is set in the [tag bitmask](crate::entries::FileEntry::tag_bitmask) of a file,
+    /// but there is no [tag entry][crate::entries::TagEntry] for it.
+    UnknownTag {
+        /// Id of the file.
+        file_id: FileId,
+        /// Index of the set bit.
+        bit: u8,
+    },
+    /// A file references a [chunking param entry][crate::entries::ChunkingParamEntry] that
+    /// does not exist.
+    UnknownChunkingParam {
+        /// Id of the file.
+        file_id: FileId,
+        /// [Chunking param id](crate::entries::FileEntry::chunking_param_id) of the file.
+        chunking_param_id: u8,
+    },
+    /// The [symlink](crate::entries::FileEntry::symlink) of a file points outside of the
+    /// install root.
+    SymlinkOutsideTree {
+        /// Id of the file.
+        file_id: FileId,
+        /// Target of the symlink.
+        symlink: String,
+    },
+    /// Two files have paths that only differ in case (or are the same), so they would overwrite
+    /// each other on case-insensitive file systems.
+    PathCollision {
+        /// Id of the first file.
+        file_id: FileId,
+        /// Id of the file it collides with.
+        other_file_id: FileId,
+        /// Path of the first file.
+        path: String,
+    },
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DuplicateFileId(id) => write!(f, "duplicate file id \"{id}\""),
+            Self::DuplicateChunkId(id) => write!(f, "duplicate chunk id \"{id}\""),
+            Self::DuplicateBundleId(id) => write!(f, "duplicate bundle id \"{id}\""),
+            Self::DuplicateDirectoryId(id) => write!(f, "duplicate directory id \"{id}\""),
+            Self::UnusedChunk {
+                chunk_id,
+                bundle_id,
+            } => write!(
+                f,
+                "chunk \"{chunk_id}\" in bundle \"{bundle_id}\" is not used by any file"
+            ),
+            Self::UnknownChunk { file_id, chunk_id } => {
+                write!(
+                    f,
+                    "file \"{file_id}\" references unknown chunk \"{chunk_id}\""
+                )
+            }
+            Self::UnknownDirectory {
+                file_id,
+                directory_id,
+            } => write!(
+                f,
+                "file \"{file_id}\" is in directory \"{directory_id}\", which could not be resolved"
+            ),
+            Self::DirectoryCycle {
+                file_id,
+                directory_id,
+            } => write!(
+                f,
+                "path of file \"{file_id}\" loops through directory \"{directory_id}\""
+            ),
+            Self::SizeMismatch {
+                file_id,
+                size,
+                chunks_size,
+            } => write!(
+                f,
+                "file \"{file_id}\" has size {size}, but its chunks add up to {chunks_size}"
+            ),
+            Self::UnknownTag { file_id, bit } => {
+                write!(f, "file \"{file_id}\" has unknown tag bit {bit} set")
+            }
+            Self::UnknownChunkingParam {
+                file_id,
+                chunking_param_id,
+            } => write!(
+                f,
+                "file \"{file_id}\" references unknown chunking param \"{chunking_param_id}\""
+            ),
+            Self::SymlinkOutsideTree { file_id, symlink } => write!(
+                f,
+                "symlink of file \"{file_id}\" points outside of the tree: \"{symlink}\""
+            ),
+            Self::PathCollision {
+                file_id,
+                other_file_id,
+                path,
+            } => write!(
+                f,
+                "path \"{path}\" of file \"{file_id}\" collides with file \"{other_file_id}\""
+            ),
+        }
+    }
+}
+
+impl ManifestData {
+    /// Checks the manifest for inconsistencies, and returns a list of [diagnostics][Diagnostic].
+    ///
+    /// Only the entries are checked, so this works the same regardless of whether
+    /// [`files`](ManifestData::files) were resolved. An empty list means no problems were found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rman::Result;
+    /// use rman::RiotManifest;
+    ///
+    /// # fn main() -> Result<()> {
+    /// let path = "file.manifest";
+    ///   # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    /// let manifest = RiotManifest::from_path(path, None)?;
+    ///
+    /// for diagnostic in manifest.data.validate() {
+    ///     println!("{diagnostic}");
+    /// }
+    ///   # Ok(())
+    /// # }
+    /// ```
+    #[must_use]
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+
+        let mut file_ids = HashSet::new();
+        for file in &self.file_entries {
+            if !file_ids.insert(file.id) {
+                diagnostics.push(Diagnostic::DuplicateFileId(file.id));
+            }
+        }
+
+        let mut bundle_ids = HashSet::new();
+        let mut chunks = HashMap::new();
+        for bundle in &self.bundle_entries {
+            if !bundle_ids.insert(bundle.id) {
+                diagnostics.push(Diagnostic::DuplicateBundleId(bundle.id));
+            }
+            for chunk in &bundle.chunks {
+                if chunks.insert(chunk.id, chunk).is_some() {
+                    diagnostics.push(Diagnostic::DuplicateChunkId(chunk.id));
+                }
+            }
+        }
+
+        let mut directories = HashMap::new();
+        for directory in &self.directory_entries {
+            if directories
+                .insert(directory.id, (directory.name.as_str(), directory.parent_id))
+                .is_some()
+            {
+                diagnostics.push(Diagnostic::DuplicateDirectoryId(directory.id));
+            }
+        }
+
+        let tag_ids: HashSet<u8> = self.tag_entries.iter().map(|t| t.id).collect();
+        let mut used_chunks = HashSet::new();
+        let mut paths: HashMap<String, FileId> = HashMap::new();
+
+        for file in &self.file_entries {
+            check_chunks(file, &chunks, &mut used_chunks, &mut diagnostics);
+
+            // bit `i` maps to the tag with the id `i + 1`, the same as in `File::parse`
+            for bit in 0..64u8 {
+                if file.tag_bitmask & (1u64 << bit) != 0 && !tag_ids.contains(&(bit + 1)) {
+                    diagnostics.push(Diagnostic::UnknownTag {
+                        file_id: file.id,
+                        bit,
+                    });
+                }
+            }
+
+            if file.chunking_param(&self.chunking_param_entries).is_none() {
+                diagnostics.push(Diagnostic::UnknownChunkingParam {
+                    file_id: file.id,
+                    chunking_param_id: file.chunking_param_id,
+                });
+            }
+
+            let mut components = match directory_components(&directories, file) {
+                Ok(components) => components,
+                Err(diagnostic) => {
+                    diagnostics.push(diagnostic);
+                    continue;
+                }
+            };
+
+            if !file.symlink.is_empty() && !symlink_stays_inside(&components, &file.symlink) {
+                diagnostics.push(Diagnostic::SymlinkOutsideTree {
+                    file_id: file.id,
+                    symlink: file.symlink.clone(),
+                });
+            }
+
+            components.push(&file.name);
+            let path = components.join("/");
+            if let Some(other_file_id) = paths.insert(path.to_lowercase(), file.id) {
+                diagnostics.push(Diagnostic::PathCollision {
+                    file_id: file.id,
+                    other_file_id,
+                    path,
+                });
+            }
+        }
+
+        for bundle in &self.bundle_entries {
+            for chunk in &bundle.chunks {
+                if !used_chunks.contains(&chunk.id) {
+                    diagnostics.push(Diagnostic::UnusedChunk {
+                        chunk_id: chunk.id,
+                        bundle_id: bundle.id,
+                    });
+                }
+            }
+        }
+
+        diagnostics
+    }
+}
+
+/// Checks that all chunks of the file exist, and that they add up to the size of the file.
+fn check_chunks(
+    file: &FileEntry,
+    chunks: &HashMap<ChunkId, &ChunkEntry>,
+    used_chunks: &mut HashSet<ChunkId>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut chunks_size = Some(0u64);
+    for chunk_id in &file.chunk_ids {
+        used_chunks.insert(*chunk_id);
+        if let Some(chunk) = chunks.get(chunk_id) {
+            chunks_size = chunks_size.map(|s| s + u64::from(chunk.uncompressed_size));
+        } else {
+            chunks_size = None;
+            diagnostics.push(Diagnostic::UnknownChunk {
+                file_id: file.id,
+                chunk_id: *chunk_id,
+            });
+        }
+    }
+
+    if let Some(chunks_size) = chunks_size {
+        if chunks_size != u64::from(file.size) {
+            diagnostics.push(Diagnostic::SizeMismatch {
+                file_id: file.id,
+                size: file.size,
+                chunks_size,
+            });
+        }
+    }
+}
+
+/// Returns names of all directories from the root to the directory of `file`, or a diagnostic
+/// if a directory is missing or the directories form a cycle.
+fn directory_components<'a>(
+    directories: &HashMap<DirectoryId, (&'a str, DirectoryId)>,
+    file: &FileEntry,
+) -> Result<Vec<&'a str>, Diagnostic> {
+    let mut components = Vec::new();
+    let mut directory_id = file.directory_id;
+
+    while directory_id != DirectoryId::ROOT {
+        let Some((name, parent_id)) = directories.get(&directory_id) else {
+            return Err(Diagnostic::UnknownDirectory {
+                file_id: file.id,
+                directory_id: file.directory_id,
+            });
+        };
+        // a path without cycles can't contain more directories than there are in total
+        if components.len() >= directories.len() {
+            return Err(Diagnostic::DirectoryCycle {
+                file_id: file.id,
+                directory_id,
+            });
+        }
+        components.push(*name);
+        directory_id = *parent_id;
+    }
+
+    components.reverse();
+    Ok(components)
+}
+
+/// Checks whether `symlink`, relative to the directory made up of `components`, stays inside
+/// the install root.
+fn symlink_stays_inside(components: &[&str], symlink: &str) -> bool {
+    if symlink.starts_with(['/', '\\']) || symlink.contains(':') {
+        return false;
+    }
+
+    let mut depth = components.len();
+    for component in symlink.split(['/', '\\']) {
+        match component {
+            "" | "." => {}
+            ".." => {
+                let Some(parent_depth) = depth.checked_sub(1) else {
+                    return false;
+                };
+                depth = parent_depth;
+            }
+            _ => depth += 1,
+        }
+    }
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::entries::{BundleEntry, ChunkingParamEntry, DirectoryEntry, TagEntry};
+
+    fn manifest() -> ManifestData {
+        ManifestData {
+            bundle_entries: vec![BundleEntry {
+                id: BundleId(1),
+                chunks: vec![ChunkEntry {
+                    id: ChunkId(10),
+                    compressed_size: 4,
+                    uncompressed_size: 8,
+                }],
+            }],
+            directory_entries: vec![DirectoryEntry {
+                id: DirectoryId(100),
+                parent_id: DirectoryId::ROOT,
+                name: "Game".to_owned(),
+            }],
+            file_entries: vec![FileEntry {
+                id: FileId(1000),
+                directory_id: DirectoryId(100),
+                size: 8,
+                name: "file.txt".to_owned(),
+                tag_bitmask: 1,
+                chunk_ids: vec![ChunkId(10)],
+                ..Default::default()
+            }],
+            tag_entries: vec![TagEntry {
+                id: 1,
+                name: "all_loc".to_owned(),
+            }],
+            chunking_param_entries: vec![ChunkingParamEntry::default()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn should_return_no_diagnostics_when_valid() {
+        assert_eq!(manifest().validate(), Vec::new());
+    }
+
+    #[test]
+    fn should_report_duplicates_and_unused_chunks() {
+        let mut data = manifest();
+        data.bundle_entries.push(data.bundle_entries[0].clone());
+        data.bundle_entries[1].chunks[0].id = ChunkId(11);
+        data.bundle_entries[1].chunks.push(ChunkEntry {
+            id: ChunkId(10),
+            ..Default::default()
+        });
+        data.directory_entries
+            .push(data.directory_entries[0].clone());
+
+        let diagnostics = data.validate();
+        assert!(diagnostics.contains(&Diagnostic::DuplicateBundleId(BundleId(1))));
+        assert!(diagnostics.contains(&Diagnostic::DuplicateChunkId(ChunkId(10))));
+        assert!(diagnostics.contains(&Diagnostic::DuplicateDirectoryId(DirectoryId(100))));
+        assert!(diagnostics.contains(&Diagnostic::UnusedChunk {
+            chunk_id: ChunkId(11),
+            bundle_id: BundleId(1)
+        }));
+    }
+
+    #[test]
+    fn should_report_file_problems() {
+        let mut data = manifest();
+        let mut file = data.file_entries[0].clone();
+        file.chunk_ids.push(ChunkId(12));
+        file.tag_bitmask = 0b10;
+        file.chunking_param_id = 1;
+        file.symlink = "../../outside".to_owned();
+        file.name = "FILE.txt".to_owned();
+        data.file_entries.push(file);
+
+        let diagnostics = data.validate();
+        assert_eq!(
+            diagnostics,
+            vec![
+                Diagnostic::DuplicateFileId(FileId(1000)),
+                Diagnostic::UnknownChunk {
+                    file_id: FileId(1000),
+                    chunk_id: ChunkId(12)
+                },
+                Diagnostic::UnknownTag {
+                    file_id: FileId(1000),
+                    bit: 1
+                },
+                Diagnostic::UnknownChunkingParam {
+                    file_id: FileId(1000),
+                    chunking_param_id: 1
+                },
+                Diagnostic::SymlinkOutsideTree {
+                    file_id: FileId(1000),
+                    symlink: "../../outside".to_owned()
+                },
+                Diagnostic::PathCollision {
+                    file_id: FileId(1000),
+                    other_file_id: FileId(1000),
+                    path: "Game/FILE.txt".to_owned()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_size_mismatch_and_unknown_directory() {
+        let mut data = manifest();
+        data.file_entries[0].size = 9;
+        data.file_entries[0].directory_id = DirectoryId(101);
+
+        assert_eq!(
+            data.validate(),
+            vec![
+                Diagnostic::SizeMismatch {
+                    file_id: FileId(1000),
+                    size: 9,
+                    chunks_size: 8
+                },
+                Diagnostic::UnknownDirectory {
+                    file_id: FileId(1000),
+                    directory_id: DirectoryId(101)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_unknown_directory_when_parent_missing() {
+        let mut data = manifest();
+        data.directory_entries[0].parent_id = DirectoryId(101);
+
+        assert_eq!(
+            data.validate(),
+            vec![Diagnostic::UnknownDirectory {
+                file_id: FileId(1000),
+                directory_id: DirectoryId(100)
+            }]
+        );
+    }
+
+    #[test]
+    fn should_report_directory_cycle() {
+        let mut data = manifest();
+        data.directory_entries[0].parent_id = DirectoryId(100);
+
+        assert_eq!(
+            data.validate(),
+            vec![Diagnostic::DirectoryCycle {
+                file_id: FileId(1000),
+                directory_id: DirectoryId(100)
+            }]
+        );
+    }
+}
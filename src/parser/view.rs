@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use crate::generated::rman::root_as_manifest_with_opts;
+use crate::generated::{Bundle, Chunk, ChunkingParam, Directory, File, Key, Manifest, Tag};
+use crate::{
+    BundleId, ChunkId, DirectoryId, FileId, Header, ManifestData, ManifestError, ParseOptions,
+    Result, RiotManifest,
+};
+
+/// Decompressed flatbuffer binary, together with the parsed header.
+///
+/// Returned by [`RiotManifest::decompress`][crate::RiotManifest::decompress] and
+/// [`RiotManifest::from_mmap`][crate::RiotManifest::from_mmap]. The flatbuffer can either be
+/// accessed lazily through a [`ManifestView`], or parsed into a [`RiotManifest`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DecompressedManifest {
+    /// Parsed file header data.
+    pub header: Header,
+    /// Decompressed flatbuffer binary.
+    pub buffer: Vec<u8>,
+}
+
+impl DecompressedManifest {
+    /// Creates a [`ManifestView`] over the [buffer](DecompressedManifest::buffer).
+    ///
+    /// # Errors
+    ///
+    /// See [`ManifestView::new`].
+    pub fn view(&self, options: Option<&ParseOptions>) -> Result<ManifestView<'_>> {
+        ManifestView::new(&self.buffer, options)
+    }
+
+    /// Parses the [buffer](DecompressedManifest::buffer) into a [`RiotManifest`].
+    ///
+    /// # Errors
+    ///
+    /// See [`ManifestData::parse`][crate::ManifestData::parse].
+    pub fn parse(&self, options: Option<&ParseOptions>) -> Result<RiotManifest> {
+        let data = ManifestData::parse(&self.buffer, options)?;
+
+        Ok(RiotManifest {
+            header: self.header,
+            data,
+        })
+    }
+}
+
+/// Borrowed view over a decompressed flatbuffer binary.
+///
+/// Unlike [`ManifestData`], the view does not copy any of the entries. Iterators and lookups
+/// return the [generated flatbuffer accessors][crate::generated], which read straight from the
+/// underlying buffer. Directories are indexed by id when the view is created, so that paths can
+/// be built and compared quickly, while the rest of the lookups are linear scans. If you need to
+/// access most of the data, convert the view to [`ManifestData`] with
+/// [`to_data`](ManifestView::to_data).
+///
+/// # Examples
+///
+/// ```rust
+/// # use rman::Result;
+/// use rman::{ManifestView, RiotManifest};
+///
+/// # fn main() -> Result<()> {
+/// let path = "file.manifest";
+///   # let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+/// let bytes = std::fs::read(path)?;
+/// let decompressed = RiotManifest::decompress(&bytes, None)?;
+///
+/// let view = ManifestView::new(&decompressed.buffer, None)?;
+/// let file = view.find_file("Test/file.txt").expect("file not found");
+/// let chunk_count = file.chunk_ids().unwrap_or_default().len();
+/// println!("{} has {chunk_count} chunks", view.file_path(&file)?);
+///   # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ManifestView<'a> {
+    manifest: Manifest<'a>,
+    directories: HashMap<DirectoryId, Directory<'a>>,
+}
+
+impl<'a> ManifestView<'a> {
+    /// Verifies the decompressed flatbuffer binary and creates a view over it.
+    ///
+    /// This allocates an index of all directories (a map from id to the borrowed directory
+    /// table), which is the only allocation the view keeps.
+    ///
+    /// Only the verifier options and the limits on the amount of files and directories from
+    /// `options` are applied, the rest are applied when calling
+    /// [`to_data`](ManifestView::to_data). If `options` is [`None`],
+    /// [`ParseOptions::default()`] is used.
+    ///
+    /// # Errors
+    ///
+    /// If verifying the flatbuffer fails, the error
+    /// [`FlatbufferError`][crate::ManifestError::FlatbufferError] is returned.
+    ///
+    /// If the amount of files or directories exceeds the configured limits, the error
+    /// [`FileCountLimitExceeded`][crate::ManifestError::FileCountLimitExceeded] or
+    /// [`DirectoryCountLimitExceeded`][crate::ManifestError::DirectoryCountLimitExceeded] is
+    /// returned.
+    pub fn new(bytes: &'a [u8], options: Option<&ParseOptions>) -> Result<Self> {
+        let default_options = ParseOptions::default();
+        let options = options.unwrap_or(&default_options);
+
+        let manifest = root_as_manifest_with_opts(&options.verifier_options(), bytes)?;
+        options.check_counts(
+            manifest.files().map_or(0, |f| f.len()),
+            manifest.directories().map_or(0, |d| d.len()),
+        )?;
+
+        let directories = manifest
+            .directories()
+            .unwrap_or_default()
+            .iter()
+            .map(|directory| (directory.id().into(), directory))
+            .collect();
+
+        Ok(Self {
+            manifest,
+            directories,
+        })
+    }
+
+    /// Returns the underlying flatbuffer table.
+    #[must_use]
+    pub const fn manifest(&self) -> Manifest<'a> {
+        self.manifest
+    }
+
+    /// Returns an iterator over all of the bundles.
+    pub fn bundles(&self) -> impl Iterator<Item = Bundle<'a>> {
+        self.manifest.bundles().unwrap_or_default().iter()
+    }
+
+    /// Returns an iterator over all of the chunks, together with the bundle they belong to.
+    pub fn chunks(&self) -> impl Iterator<Item = (Bundle<'a>, Chunk<'a>)> {
+        self.bundles().flat_map(|bundle| {
+            bundle
+                .chunks()
+                .unwrap_or_default()
+                .iter()
+                .map(move |chunk| (bundle, chunk))
+        })
+    }
+
+    /// Returns an iterator over all of the files.
+    pub fn files(&self) -> impl Iterator<Item = File<'a>> {
+        self.manifest.files().unwrap_or_default().iter()
+    }
+
+    /// Returns an iterator over all of the directories.
+    pub fn directories(&self) -> impl Iterator<Item = Directory<'a>> {
+        self.manifest.directories().unwrap_or_default().iter()
+    }
+
+    /// Returns an iterator over all of the keys.
+    pub fn keys(&self) -> impl Iterator<Item = Key<'a>> {
+        self.manifest.keys().unwrap_or_default().iter()
+    }
+
+    /// Returns an iterator over all of the tags.
+    pub fn tags(&self) -> impl Iterator<Item = Tag<'a>> {
+        self.manifest.tags().unwrap_or_default().iter()
+    }
+
+    /// Returns an iterator over all of the chunking params.
+    pub fn chunking_params(&self) -> impl Iterator<Item = ChunkingParam<'a>> {
+        self.manifest.chunking_params().unwrap_or_default().iter()
+    }
+
+    /// Returns the bundle with the provided id.
+    #[must_use]
+    pub fn bundle(&self, id: BundleId) -> Option<Bundle<'a>> {
+        self.bundles()
+            .find(|bundle| BundleId::from(bundle.id()) == id)
+    }
+
+    /// Returns the chunk with the provided id, together with the bundle it belongs to.
+    #[must_use]
+    pub fn chunk(&self, id: ChunkId) -> Option<(Bundle<'a>, Chunk<'a>)> {
+        self.chunks()
+            .find(|(_, chunk)| ChunkId::from(chunk.id()) == id)
+    }
+
+    /// Returns the file with the provided id.
+    #[must_use]
+    pub fn file(&self, id: FileId) -> Option<File<'a>> {
+        self.files().find(|file| FileId::from(file.id()) == id)
+    }
+
+    /// Returns the directory with the provided id.
+    #[must_use]
+    pub fn directory(&self, id: DirectoryId) -> Option<Directory<'a>> {
+        self.directories.get(&id).copied()
+    }
+
+    /// Returns the file whose [path](crate::File::path) equals `path`.
+    ///
+    /// Paths are compared without building them, using the directory index built in
+    /// [`new`](ManifestView::new), so this does not allocate.
+    #[must_use]
+    pub fn find_file(&self, path: &str) -> Option<File<'a>> {
+        self.files().find(|file| self.path_equals(file, path))
+    }
+
+    /// Builds the [path](crate::File::path) of the provided file, the same way as
+    /// [`File::parse`][crate::File::parse].
+    ///
+    /// # Errors
+    ///
+    /// If a directory does not exist, the error
+    /// [`FileParseError`][crate::ManifestError::FileParseError] is returned.
+    ///
+    /// If the directories form a cycle, the error
+    /// [`DirectoryCycle`][crate::ManifestError::DirectoryCycle] is returned.
+    pub fn file_path(&self, file: &File<'a>) -> Result<String> {
+        let mut names = Vec::new();
+        let mut directory_id = DirectoryId::from(file.directory_id());
+
+        while directory_id != DirectoryId::ROOT {
+            let Some(directory) = self.directory(directory_id) else {
+                let message =
+                    format!("could not find a directory with the following id: \"{directory_id}\"");
+                return Err(ManifestError::FileParseError(message));
+            };
+            // a path without cycles can't contain more directories than there are in total
+            if names.len() >= self.directories.len() {
+                return Err(ManifestError::DirectoryCycle(directory_id));
+            }
+            names.push(directory.name().unwrap_or_default());
+            directory_id = directory.parent_id().into();
+        }
+
+        let name = file.name().unwrap_or_default();
+        let length = names.iter().map(|n| n.len() + 1).sum::<usize>() + name.len();
+        let mut path = String::with_capacity(length);
+        for directory_name in names.into_iter().rev() {
+            path.push_str(directory_name);
+            path.push('/');
+        }
+        path.push_str(name);
+
+        Ok(path)
+    }
+
+    /// Copies all of the entries into an owned [`ManifestData`].
+    ///
+    /// # Errors
+    ///
+    /// See [`ManifestData::parse`][crate::ManifestData::parse], excluding the
+    /// [`FlatbufferError`][crate::ManifestError::FlatbufferError].
+    pub fn to_data(&self, options: Option<&ParseOptions>) -> Result<ManifestData> {
+        let default_options = ParseOptions::default();
+        let options = options.unwrap_or(&default_options);
+
+        ManifestData::from_manifest(self.manifest, options)
+    }
+
+    fn path_equals(&self, file: &File<'a>, path: &str) -> bool {
+        let directory_count = self.directories.len();
+        let Some(mut rest) = path.strip_suffix(file.name().unwrap_or_default()) else {
+            return false;
+        };
+        let mut directory_id = DirectoryId::from(file.directory_id());
+        let mut depth = 0;
+
+        while directory_id != DirectoryId::ROOT {
+            let Some(directory) = self.directory(directory_id) else {
+                return false;
+            };
+            depth += 1;
+            if depth > directory_count {
+                return false;
+            }
+            let Some(parent_rest) = rest
+                .strip_suffix('/')
+                .and_then(|r| r.strip_suffix(directory.name().unwrap_or_default()))
+            else {
+                return false;
+            };
+            rest = parent_rest;
+            directory_id = directory.parent_id().into();
+        }
+
+        rest.is_empty()
+    }
+}
@@ -0,0 +1,196 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::{ManifestError, Result};
+
+/// How strictly a [`ManifestPath`] is validated.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathStrictness {
+    /// Any component that is not a plain name is rejected.
+    ///
+    /// This includes empty components (leading, trailing or repeated `/`), `.` and `..`
+    /// components and backslashes.
+    #[default]
+    Strict,
+    /// Components are normalized where this can be done safely.
+    ///
+    /// Backslashes are treated as separators, empty and `.` components are dropped, and `..`
+    /// removes the previous component. A `..` that would escape the root is still rejected.
+    Normalize,
+}
+
+/// Relative path of a [file](crate::File) that is safe to write to disk.
+///
+/// The path is guaranteed to consist of at least one component, where no component is empty,
+/// `.` or `..`, or contains a NUL byte, a `:` (Windows drive letters and alternate data
+/// streams), a `/` or a `\`. This means that joining it to an install root can never result in
+/// a path outside of that root.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rman::Result;
+/// use rman::{ManifestPath, PathStrictness};
+///
+/// # fn main() -> Result<()> {
+/// let path = ManifestPath::new("Game//./DATA/file.txt", PathStrictness::Normalize)?;
+/// assert_eq!(path.as_str(), "Game/DATA/file.txt");
+///
+/// assert!(ManifestPath::new("Game//./DATA/file.txt", PathStrictness::Strict).is_err());
+/// assert!(ManifestPath::new("../file.txt", PathStrictness::Normalize).is_err());
+///   # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ManifestPath(String);
+
+impl ManifestPath {
+    /// Validates the provided path, normalizing it if `strictness` is
+    /// [`PathStrictness::Normalize`].
+    ///
+    /// # Errors
+    ///
+    /// If the path is empty, would escape the root, or contains a component that is not allowed
+    /// with the provided `strictness`, the error
+    /// [`UnsafePath`][crate::ManifestError::UnsafePath] is returned.
+    pub fn new(path: &str, strictness: PathStrictness) -> Result<Self> {
+        let unsafe_path = |reason: &str| ManifestError::UnsafePath(format!("\"{path}\": {reason}"));
+
+        if path.contains('\0') {
+            return Err(unsafe_path("contains a NUL byte"));
+        }
+        if path.contains(':') {
+            return Err(unsafe_path("contains a \":\""));
+        }
+
+        let mut components: Vec<&str> = Vec::new();
+
+        match strictness {
+            PathStrictness::Strict => {
+                if path.contains('\\') {
+                    return Err(unsafe_path("contains a backslash"));
+                }
+                for component in path.split('/') {
+                    match component {
+                        "" => return Err(unsafe_path("contains an empty component")),
+                        "." | ".." => {
+                            return Err(unsafe_path("contains a relative component"));
+                        }
+                        _ => components.push(component),
+                    }
+                }
+            }
+            PathStrictness::Normalize => {
+                for component in path.split(['/', '\\']) {
+                    match component {
+                        "" | "." => {}
+                        ".." => {
+                            if components.pop().is_none() {
+                                return Err(unsafe_path("escapes the root"));
+                            }
+                        }
+                        _ => components.push(component),
+                    }
+                }
+            }
+        }
+
+        if components.is_empty() {
+            return Err(unsafe_path("is empty"));
+        }
+
+        Ok(Self(components.join("/")))
+    }
+
+    /// Returns the path as a string, with components separated by `/`.
+    #[must_use]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns an iterator over the components of the path.
+    pub fn components(&self) -> impl Iterator<Item = &str> {
+        self.0.split('/')
+    }
+
+    /// Joins the path to `root`, using the separators of the current platform.
+    #[must_use]
+    pub fn join_to<P: AsRef<Path>>(&self, root: P) -> PathBuf {
+        let mut path = root.as_ref().to_path_buf();
+        path.extend(self.components());
+        path
+    }
+}
+
+impl fmt::Display for ManifestPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for ManifestPath {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_accept_when_plain_path() {
+        for strictness in [PathStrictness::Strict, PathStrictness::Normalize] {
+            let path = ManifestPath::new("Game/DATA/file.txt", strictness).unwrap();
+            assert_eq!(path.as_str(), "Game/DATA/file.txt", "path did not match");
+            assert_eq!(
+                path.components().collect::<Vec<_>>(),
+                ["Game", "DATA", "file.txt"],
+                "components did not match"
+            );
+        }
+    }
+
+    #[test]
+    fn should_error_when_strict_and_unsafe_component() {
+        for path in [
+            "",
+            "/etc/passwd",
+            "Game//file.txt",
+            "Game/",
+            "./file.txt",
+            "../file.txt",
+            "Game/../../file.txt",
+            "Game\\file.txt",
+            "C:/file.txt",
+            "file.txt\0",
+        ] {
+            let Err(error) = ManifestPath::new(path, PathStrictness::Strict) else {
+                panic!("did not throw an error for {path:?}");
+            };
+            let ManifestError::UnsafePath(..) = error else {
+                panic!("some other error was thrown");
+            };
+        }
+    }
+
+    #[test]
+    fn should_normalize_when_lenient() {
+        for (path, expected) in [
+            ("/Game/file.txt", "Game/file.txt"),
+            ("Game//./file.txt", "Game/file.txt"),
+            ("Game\\DATA\\file.txt", "Game/DATA/file.txt"),
+            ("Game/DATA/../file.txt", "Game/file.txt"),
+        ] {
+            let normalized = ManifestPath::new(path, PathStrictness::Normalize).unwrap();
+            assert_eq!(normalized.as_str(), expected, "path did not match");
+        }
+
+        for path in ["", "/", "../file.txt", "Game/../..", "C:\\file.txt", "a\0b"] {
+            assert!(
+                ManifestPath::new(path, PathStrictness::Normalize).is_err(),
+                "did not throw an error for {path:?}"
+            );
+        }
+    }
+}
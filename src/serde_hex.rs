@@ -0,0 +1,128 @@
+//! Serde helpers that (de)serialize ids as hex strings.
+//!
+//! By default, ids are (de)serialized as integers. Fields that should instead use the 16
+//! character uppercase hex form (e.g. `"0123456789ABCDEF"`), which is how Riot displays ids,
+//! can opt in with `#[serde(with = "rman::serde_hex")]`. The helpers work with
+//! [`BundleId`][crate::BundleId], [`ChunkId`][crate::ChunkId], [`FileId`][crate::FileId],
+//! [`DirectoryId`][crate::DirectoryId], [`i64`] and [`u64`] (e.g. a
+//! [manifest id](crate::Header::manifest_id)).
+//!
+//! Hex strings are only used by [human-readable][serde::Serializer::is_human_readable] formats
+//! like JSON. Binary formats (e.g. bincode or postcard) store the id as an integer, the same as
+//! without the helpers.
+//!
+//! # Examples
+//!
+//! ```rust
+//! use rman::BundleId;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct InstalledBundle {
+//!     #[serde(with = "rman::serde_hex")]
+//!     manifest_id: u64,
+//!     #[serde(with = "rman::serde_hex")]
+//!     bundle_id: BundleId,
+//! }
+//!
+//! let bundle = InstalledBundle {
+//!     manifest_id: 0x0123_4567_89AB_CDEF,
+//!     bundle_id: BundleId(0xFF),
+//! };
+//! let json = serde_json::to_string(&bundle).unwrap();
+//! assert_eq!(
+//!     json,
+//!     r#"{"manifest_id":"0123456789ABCDEF","bundle_id":"00000000000000FF"}"#
+//! );
+//! ```
+
+use std::fmt;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, Visitor};
+use serde::{Deserializer, Serialize, Serializer};
+
+/// Ids that can be (de)serialized with the helpers in this module.
+///
+/// This trait is sealed, and can't be implemented outside of this crate.
+pub trait HexId: Copy + Serialize + DeserializeOwned + sealed::Sealed {
+    #[doc(hidden)]
+    fn to_bits(self) -> u64;
+    #[doc(hidden)]
+    fn from_bits(bits: u64) -> Self;
+}
+
+pub(crate) mod sealed {
+    pub trait Sealed {}
+}
+
+impl sealed::Sealed for i64 {}
+
+impl HexId for i64 {
+    fn to_bits(self) -> u64 {
+        u64::from_le_bytes(self.to_le_bytes())
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        Self::from_le_bytes(bits.to_le_bytes())
+    }
+}
+
+impl sealed::Sealed for u64 {}
+
+impl HexId for u64 {
+    fn to_bits(self) -> u64 {
+        self
+    }
+
+    fn from_bits(bits: u64) -> Self {
+        bits
+    }
+}
+
+/// Serializes the id as a hex string in human-readable formats, and as an integer otherwise.
+///
+/// # Errors
+///
+/// Propagates errors from the serializer.
+pub fn serialize<T: HexId, S: Serializer>(value: &T, serializer: S) -> Result<S::Ok, S::Error> {
+    if serializer.is_human_readable() {
+        serializer.collect_str(&format_args!("{:016X}", value.to_bits()))
+    } else {
+        value.serialize(serializer)
+    }
+}
+
+/// Deserializes the id from a hex string in human-readable formats, and from an integer
+/// otherwise.
+///
+/// # Errors
+///
+/// If the hex string is not made of 1 to 16 hex digits, or the deserializer fails, an error is
+/// returned.
+pub fn deserialize<'de, T: HexId, D: Deserializer<'de>>(deserializer: D) -> Result<T, D::Error> {
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(HexVisitor(PhantomData))
+    } else {
+        T::deserialize(deserializer)
+    }
+}
+
+struct HexVisitor<T>(PhantomData<T>);
+
+impl<T: HexId> Visitor<'_> for HexVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a hex string of 1 to 16 digits")
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<T, E> {
+        // `from_str_radix` would also accept a leading `+`
+        if value.is_empty() || value.len() > 16 || !value.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(E::invalid_value(de::Unexpected::Str(value), &self));
+        }
+        u64::from_str_radix(value, 16)
+            .map(T::from_bits)
+            .map_err(|_| E::invalid_value(de::Unexpected::Str(value), &self))
+    }
+}
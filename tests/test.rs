@@ -104,3 +104,958 @@ pub fn should_have_correct_values_when_valid_empty_manifest() {
         "should have 0 chunking param entries"
     );
 }
+
+#[test]
+pub fn should_parse_same_manifest_from_bytes_and_stream() {
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let bytes = std::fs::read(path).unwrap();
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+
+    let from_bytes = RiotManifest::from_bytes(&bytes, None).unwrap();
+    assert_eq!(from_bytes, manifest, "manifest from bytes did not match");
+
+    let from_stream = RiotManifest::from_stream(&bytes[..], None).unwrap();
+    assert_eq!(from_stream, manifest, "manifest from stream did not match");
+}
+
+#[test]
+pub fn should_error_when_stream_is_truncated() {
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let bytes = std::fs::read(path).unwrap();
+
+    let Err(error) = RiotManifest::from_stream(&bytes[..bytes.len() - 1], None) else {
+        panic!("did not throw an error");
+    };
+    let rman::ManifestError::IoError(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[test]
+pub fn should_error_when_uncompressed_size_does_not_match() {
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let mut bytes = std::fs::read(path).unwrap();
+    let uncompressed_size = u32::from_le_bytes(bytes[24..28].try_into().unwrap());
+    bytes[24..28].copy_from_slice(&(uncompressed_size + 1).to_le_bytes());
+
+    let Err(error) = RiotManifest::from_stream(&bytes[..], None) else {
+        panic!("did not throw an error");
+    };
+    let rman::ManifestError::UncompressedSizeMismatch(..) = error else {
+        panic!("some other error was thrown");
+    };
+
+    let Err(error) = RiotManifest::from_bytes(&bytes, None) else {
+        panic!("did not throw an error");
+    };
+    let rman::ManifestError::UncompressedSizeMismatch(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[test]
+pub fn should_error_when_uncompressed_size_is_forged() {
+    use rman::{ManifestError, ParseOptions};
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let mut bytes = std::fs::read(path).unwrap();
+    // claim an uncompressed size of 4 GiB, which must not be allocated up front
+    bytes[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let options = ParseOptions::new().with_max_uncompressed_size(None);
+    let Err(error) = RiotManifest::from_bytes(&bytes, Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::UncompressedSizeMismatch(u32::MAX, _) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[test]
+pub fn should_error_when_size_limits_exceeded() {
+    use rman::{ManifestError, ParseOptions};
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+
+    let options = ParseOptions::new().with_max_compressed_size(Some(1));
+    let Err(error) = RiotManifest::from_path(path, Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::CompressedSizeLimitExceeded(..) = error else {
+        panic!("some other error was thrown");
+    };
+
+    let options = ParseOptions::new().with_max_uncompressed_size(Some(1));
+    let Err(error) = RiotManifest::from_path(path, Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::UncompressedSizeLimitExceeded(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[test]
+pub fn should_error_when_resource_limits_exceeded() {
+    use rman::{ManifestError, ParseOptions};
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+
+    let options = ParseOptions::new().with_max_files(Some(0));
+    let Err(error) = RiotManifest::from_path(path, Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::FileCountLimitExceeded(1) = error else {
+        panic!("some other error was thrown");
+    };
+
+    let options = ParseOptions::new().with_max_directories(Some(0));
+    let Err(error) = RiotManifest::from_path(path, Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::DirectoryCountLimitExceeded(..) = error else {
+        panic!("some other error was thrown");
+    };
+
+    let options = ParseOptions::new().with_max_path_length(Some(4));
+    let Err(error) = RiotManifest::from_path(path, Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::PathLengthLimitExceeded(..) = error else {
+        panic!("some other error was thrown");
+    };
+
+    let options = ParseOptions::new()
+        .with_max_chunk_size(Some(1))
+        .with_resolve_files(false);
+    let Err(error) = RiotManifest::from_path(path, Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::ChunkSizeLimitExceeded(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[test]
+pub fn should_enforce_default_limits_when_no_options() {
+    use rman::ManifestError;
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let mut bytes = std::fs::read(path).unwrap();
+    // claim an uncompressed size of 4 GiB
+    bytes[24..28].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let Err(error) = RiotManifest::from_bytes(&bytes, None) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::UncompressedSizeLimitExceeded(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[test]
+pub fn should_error_when_chunk_offsets_overflow() {
+    use rman::entries::ChunkEntry;
+    use rman::{ChunkId, ManifestError, ParseOptions};
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let mut manifest = RiotManifest::from_path(path, None).unwrap();
+    let chunks = &mut manifest.data.bundle_entries[0].chunks;
+    for id in [1, 2] {
+        chunks.push(ChunkEntry {
+            id: ChunkId(id),
+            compressed_size: u32::MAX,
+            uncompressed_size: 0,
+        });
+    }
+
+    let options = ParseOptions::new().with_max_chunk_size(None);
+    let Err(error) = manifest.data.resolve_files(Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::InvalidChunkRange(ChunkId(1)) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[tokio::test]
+pub async fn should_error_when_downloaded_chunk_is_empty() {
+    use rman::{Downloader, ManifestError};
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let mut file = manifest.data.files[0].clone();
+    file.chunks[0].compressed_size = 0;
+
+    // no request should be sent, so the url does not need to exist
+    let downloader = Downloader::new(reqwest::Client::new());
+    let Err(error) = downloader
+        .download_file(&file, Vec::new(), "http://127.0.0.1:1")
+        .await
+    else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::InvalidChunkRange(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[test]
+pub fn should_error_when_directories_form_a_cycle() {
+    use rman::entries::{DirectoryEntry, FileEntry};
+    use rman::{DirectoryId, FileId, ManifestData, ManifestError, ParseOptions};
+
+    let directory = |id, parent_id| DirectoryEntry {
+        id: DirectoryId(id),
+        parent_id: DirectoryId(parent_id),
+        name: format!("dir{id}"),
+    };
+    let mut data = ManifestData {
+        directory_entries: vec![directory(1, 2), directory(2, 3), directory(3, 1)],
+        file_entries: vec![FileEntry {
+            id: FileId(1),
+            directory_id: DirectoryId(1),
+            name: "file.txt".to_owned(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+
+    let Err(error) = data.resolve_files(None) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::DirectoryCycle(..) = error else {
+        panic!("some other error was thrown");
+    };
+
+    data.directory_entries[2].parent_id = DirectoryId::ROOT;
+    let files = data.resolve_files(None).unwrap();
+    assert_eq!(
+        files[0].path, "dir3/dir2/dir1/file.txt",
+        "path did not match"
+    );
+
+    let options = ParseOptions::new().with_max_directory_depth(Some(2));
+    let Err(error) = data.resolve_files(Some(&options)) else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::DirectoryDepthLimitExceeded(3) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[test]
+pub fn should_skip_unresolved_files_when_lenient() {
+    use rman::entries::{BundleEntry, ChunkEntry, DirectoryEntry, FileEntry};
+    use rman::{BundleId, ChunkId, Diagnostic, DirectoryId, FileId, ManifestData, ParseOptions};
+
+    let data = ManifestData {
+        bundle_entries: vec![BundleEntry {
+            id: BundleId(1),
+            chunks: vec![ChunkEntry {
+                id: ChunkId(10),
+                ..Default::default()
+            }],
+        }],
+        directory_entries: vec![
+            DirectoryEntry {
+                id: DirectoryId(100),
+                parent_id: DirectoryId::ROOT,
+                name: "Game".to_owned(),
+            },
+            DirectoryEntry {
+                id: DirectoryId(102),
+                parent_id: DirectoryId(102),
+                name: "Loop".to_owned(),
+            },
+        ],
+        file_entries: vec![
+            FileEntry {
+                id: FileId(1),
+                directory_id: DirectoryId(100),
+                name: "valid.txt".to_owned(),
+                chunk_ids: vec![ChunkId(10)],
+                ..Default::default()
+            },
+            FileEntry {
+                id: FileId(2),
+                directory_id: DirectoryId(100),
+                name: "missing_chunk.txt".to_owned(),
+                chunk_ids: vec![ChunkId(10), ChunkId(11)],
+                ..Default::default()
+            },
+            FileEntry {
+                id: FileId(3),
+                directory_id: DirectoryId(101),
+                name: "missing_directory.txt".to_owned(),
+                ..Default::default()
+            },
+            FileEntry {
+                id: FileId(4),
+                directory_id: DirectoryId(102),
+                name: "directory_cycle.txt".to_owned(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+
+    assert!(data.resolve_files(None).is_err(), "did not throw an error");
+
+    let options = ParseOptions::new().with_lenient(true);
+    let (files, warnings) = data.resolve_files_with_warnings(Some(&options)).unwrap();
+    assert_eq!(files.len(), 1, "amount of files did not match");
+    assert_eq!(files[0].path, "Game/valid.txt", "path did not match");
+    assert_eq!(
+        warnings,
+        [
+            Diagnostic::UnknownChunk {
+                file_id: FileId(2),
+                chunk_id: ChunkId(11)
+            },
+            Diagnostic::UnknownDirectory {
+                file_id: FileId(3),
+                directory_id: DirectoryId(101)
+            },
+            Diagnostic::DirectoryCycle {
+                file_id: FileId(4),
+                directory_id: DirectoryId(102)
+            },
+        ],
+        "warnings did not match"
+    );
+}
+
+#[tokio::test]
+pub async fn should_error_when_downloaded_chunk_too_large() {
+    use rman::{Downloader, ManifestError};
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let file = &manifest.data.files[0];
+
+    // no request should be sent, so the url does not need to exist
+    let downloader = Downloader::new(reqwest::Client::new()).with_max_chunk_size(Some(1));
+    let Err(error) = downloader
+        .download_file(file, Vec::new(), "http://127.0.0.1:1")
+        .await
+    else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::ChunkSizeLimitExceeded(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[tokio::test]
+pub async fn should_download_file_when_using_shared_client() {
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use rman::DownloadConfig;
+
+    let bundle = std::fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/bundles/0000000000000000.bundle",
+        ))
+        .times(2)
+        .respond_with(status_code(200).body(bundle)),
+    );
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let url = server.url("/bundles").to_string();
+    let client = DownloadConfig::default().build_client().unwrap();
+
+    for _ in 0..2 {
+        let mut buf = Vec::new();
+        manifest.data.files[0]
+            .download_with_client(&client, &mut buf, url.as_str())
+            .await
+            .unwrap();
+        assert_eq!(buf, b"TEST", "downloaded data did not match");
+    }
+}
+
+#[tokio::test]
+pub async fn should_use_max_chunk_size_when_downloader_built_from_config() {
+    use rman::{DownloadConfig, Downloader, ManifestError};
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let file = &manifest.data.files[0];
+
+    assert_eq!(
+        DownloadConfig::default().max_chunk_size,
+        Some(64 * 1024 * 1024),
+        "default config should limit the chunk size"
+    );
+
+    // no request should be sent, so the url does not need to exist
+    let config = DownloadConfig {
+        max_chunk_size: Some(1),
+        ..Default::default()
+    };
+    let downloader = Downloader::from_config(&config).unwrap();
+    let Err(error) = downloader
+        .download_file(file, Vec::new(), "http://127.0.0.1:1")
+        .await
+    else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::ChunkSizeLimitExceeded(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[tokio::test]
+pub async fn should_report_progress_and_retry_when_request_fails() {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use rman::{Downloader, ProgressEvent, ProgressEventKind};
+
+    let bundle = std::fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+    let bundle_size = bundle.len();
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/bundles/0000000000000000.bundle",
+        ))
+        .times(2)
+        .respond_with(httptest::cycle![
+            status_code(503),
+            status_code(200).body(bundle),
+        ]),
+    );
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let url = server.url("/bundles").to_string();
+
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let sink_events = Arc::clone(&events);
+    let downloader = Downloader::new(reqwest::Client::new())
+        .with_max_retries(2)
+        .with_retry_delay(Duration::from_millis(1))
+        .with_progress(move |event: &ProgressEvent<'_>| {
+            let kind = match &event.kind {
+                ProgressEventKind::Retry {
+                    chunk_index,
+                    attempt,
+                    ..
+                } => format!("Retry({chunk_index}, {attempt})"),
+                kind => format!("{kind:?}"),
+            };
+            sink_events.lock().unwrap().push(kind);
+        });
+
+    let mut buf = Vec::new();
+    downloader
+        .download_file(&manifest.data.files[0], &mut buf, url.as_str())
+        .await
+        .unwrap();
+    assert_eq!(buf, b"TEST", "downloaded data did not match");
+
+    assert_eq!(
+        *events.lock().unwrap(),
+        [
+            "FileStarted".to_owned(),
+            "Retry(0, 1)".to_owned(),
+            format!("ChunkDownloaded {{ chunk_index: 0, bytes: {bundle_size} }}"),
+            "ChunkDecompressed { chunk_index: 0, bytes: 4 }".to_owned(),
+            "ChunkCompleted { chunk_index: 0, bytes: 4 }".to_owned(),
+            "FileCompleted".to_owned(),
+        ],
+        "progress events did not match"
+    );
+}
+
+#[tokio::test]
+pub async fn should_rate_limit_chunks_but_not_retries_when_using_limiter() {
+    use std::time::{Duration, Instant};
+
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use rman::{Downloader, RateLimiter};
+
+    let bundle = std::fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/bundles/0000000000000000.bundle",
+        ))
+        .times(3)
+        .respond_with(httptest::cycle![
+            status_code(503),
+            status_code(200).body(bundle.clone()),
+            status_code(200).body(bundle),
+        ]),
+    );
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let url = server.url("/bundles").to_string();
+
+    // the bucket starts with a single token, so only the first chunk goes through straight away
+    let downloader = Downloader::new(reqwest::Client::new())
+        .with_max_retries(1)
+        .with_retry_delay(Duration::from_millis(1))
+        .with_rate_limiter(RateLimiter::new(None, Some(1)));
+
+    let started_at = Instant::now();
+    downloader
+        .download_file(&manifest.data.files[0], Vec::new(), url.as_str())
+        .await
+        .unwrap();
+    assert!(
+        started_at.elapsed() < Duration::from_millis(500),
+        "retry should not wait for the limiter"
+    );
+
+    let started_at = Instant::now();
+    downloader
+        .download_file(&manifest.data.files[0], Vec::new(), url.as_str())
+        .await
+        .unwrap();
+    assert!(
+        started_at.elapsed() >= Duration::from_millis(500),
+        "second chunk should wait for the limiter"
+    );
+}
+
+#[tokio::test]
+pub async fn should_not_retry_when_error_is_permanent() {
+    use std::time::Duration;
+
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use rman::{Downloader, ManifestError};
+
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/bundles/0000000000000000.bundle",
+        ))
+        .times(1)
+        .respond_with(status_code(404)),
+    );
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let url = server.url("/bundles").to_string();
+
+    let downloader = Downloader::new(reqwest::Client::new())
+        .with_max_retries(3)
+        .with_retry_delay(Duration::from_millis(1));
+    let Err(error) = downloader
+        .download_file(&manifest.data.files[0], Vec::new(), url.as_str())
+        .await
+    else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::ReqwestError(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[tokio::test]
+pub async fn should_skip_completed_chunks_when_resuming_download() {
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use rman::{
+        CancellationToken, DownloadState, Downloader, ManifestError, ProgressEvent,
+        ProgressEventKind,
+    };
+
+    let bundle = std::fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+    let server = Server::run();
+    // one request for each run, the first chunk must not be requested again
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/bundles/0000000000000000.bundle",
+        ))
+        .times(2)
+        .respond_with(status_code(200).body(bundle)),
+    );
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let url = server.url("/bundles").to_string();
+
+    let mut file = manifest.data.files[0].clone();
+    file.chunks = vec![file.chunks[0]; 2];
+    let files = [file];
+
+    let root = concat!(env!("OUT_DIR"), "/should_skip_completed_chunks");
+    let state_path = concat!(env!("OUT_DIR"), "/should_skip_completed_chunks.state");
+    let _ = std::fs::remove_dir_all(root);
+    let _ = std::fs::remove_file(state_path);
+
+    // cancel once the first chunk is written
+    let token = CancellationToken::new();
+    let sink_token = token.clone();
+    let downloader = Downloader::new(reqwest::Client::new())
+        .with_cancellation(token)
+        .with_progress(move |event: &ProgressEvent<'_>| {
+            if let ProgressEventKind::ChunkCompleted { .. } = event.kind {
+                sink_token.cancel();
+            }
+        });
+    let mut state = DownloadState::open(state_path, manifest.header.manifest_id).unwrap();
+    let Err(error) = downloader
+        .download_files_resumable(&files, root, url.as_str(), &mut state)
+        .await
+    else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::Cancelled = error else {
+        panic!("some other error was thrown");
+    };
+
+    let file_path = format!("{root}/Test/file.txt");
+    let state = DownloadState::open(state_path, manifest.header.manifest_id).unwrap();
+    assert_eq!(state.completed_chunks(&files[0]), 1, "state did not match");
+    assert_eq!(
+        std::fs::read(&file_path).unwrap(),
+        b"TEST",
+        "file did not match"
+    );
+
+    // mark the first chunk, so that rewriting it would be noticed
+    std::fs::write(&file_path, b"DONE").unwrap();
+
+    let mut state = DownloadState::open(state_path, manifest.header.manifest_id).unwrap();
+    Downloader::new(reqwest::Client::new())
+        .download_files_resumable(&files, root, url.as_str(), &mut state)
+        .await
+        .unwrap();
+    assert!(state.is_completed(&files[0]), "file was not completed");
+    assert_eq!(
+        std::fs::read(&file_path).unwrap(),
+        b"DONETEST",
+        "file did not match"
+    );
+    state.remove().unwrap();
+}
+
+#[test]
+pub fn should_match_parsed_manifest_when_using_view() {
+    use rman::ManifestView;
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let bytes = std::fs::read(path).unwrap();
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+
+    let decompressed = RiotManifest::decompress(&bytes, None).unwrap();
+    assert_eq!(decompressed.header, manifest.header, "header did not match");
+
+    let view = ManifestView::new(&decompressed.buffer, None).unwrap();
+    assert_eq!(
+        view.to_data(None).unwrap(),
+        manifest.data,
+        "data from view did not match"
+    );
+
+    let file = &manifest.data.files[0];
+    let file_view = view.find_file(&file.path).unwrap();
+    assert_eq!(file_view.id(), file.id.get(), "file id did not match");
+    assert_eq!(
+        view.file_path(&file_view).unwrap(),
+        file.path,
+        "file path did not match"
+    );
+    assert!(view.find_file("Test/missing.txt").is_none(), "found a file");
+    assert_eq!(
+        view.chunks().count(),
+        manifest
+            .data
+            .bundle_entries
+            .iter()
+            .map(|b| b.chunks.len())
+            .sum::<usize>(),
+        "amount of chunks did not match"
+    );
+}
+
+#[test]
+pub fn should_tell_missing_directory_from_cycle_when_using_view() {
+    use rman::entries::{DirectoryEntry, FileEntry};
+    use rman::{DirectoryId, FileId, ManifestData, ManifestError, ManifestView};
+
+    let data = ManifestData {
+        directory_entries: vec![
+            DirectoryEntry {
+                id: DirectoryId(1),
+                parent_id: DirectoryId(7),
+                name: "Missing".to_owned(),
+            },
+            DirectoryEntry {
+                id: DirectoryId(2),
+                parent_id: DirectoryId(2),
+                name: "Loop".to_owned(),
+            },
+        ],
+        file_entries: vec![
+            FileEntry {
+                id: FileId(1),
+                directory_id: DirectoryId(1),
+                name: "missing_parent.txt".to_owned(),
+                ..Default::default()
+            },
+            FileEntry {
+                id: FileId(2),
+                directory_id: DirectoryId(2),
+                name: "directory_cycle.txt".to_owned(),
+                ..Default::default()
+            },
+        ],
+        ..Default::default()
+    };
+    let buffer = data.to_flatbuffer();
+    let view = ManifestView::new(&buffer, None).unwrap();
+    let files: Vec<_> = view.files().collect();
+
+    let Err(ManifestError::FileParseError(..)) = view.file_path(&files[0]) else {
+        panic!("missing parent directory was not reported");
+    };
+    let Err(ManifestError::DirectoryCycle(DirectoryId(2))) = view.file_path(&files[1]) else {
+        panic!("directory cycle was not reported");
+    };
+    assert!(
+        view.find_file("Missing/missing_parent.txt").is_none(),
+        "found a file"
+    );
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+pub fn should_parse_same_manifest_from_mmap() {
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+
+    let decompressed = RiotManifest::from_mmap(path, None).unwrap();
+    assert_eq!(
+        decompressed.parse(None).unwrap(),
+        manifest,
+        "manifest from mmap did not match"
+    );
+}
+
+#[tokio::test]
+pub async fn should_parse_same_manifest_from_async_reader() {
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let bytes = std::fs::read(path).unwrap();
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+
+    let from_async_reader = RiotManifest::from_async_reader(&bytes[..], None)
+        .await
+        .unwrap();
+    assert_eq!(
+        from_async_reader, manifest,
+        "manifest from async reader did not match"
+    );
+}
+
+#[tokio::test]
+pub async fn should_error_when_async_reader_is_shorter_than_compressed_size() {
+    use rman::{ManifestError, ParseOptions};
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let mut bytes = std::fs::read(path).unwrap();
+    // claim a compressed size of 4 GiB, which must not be allocated up front
+    bytes[12..16].copy_from_slice(&u32::MAX.to_le_bytes());
+
+    let options = ParseOptions::new().with_max_compressed_size(None);
+    let Err(error) = RiotManifest::from_async_reader(&bytes[..], Some(&options)).await else {
+        panic!("did not throw an error");
+    };
+    let ManifestError::IoError(..) = error else {
+        panic!("some other error was thrown");
+    };
+}
+
+#[tokio::test]
+pub async fn should_use_chunk_cache_when_downloading_twice() {
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use rman::{ChunkCache, Downloader};
+
+    let bundle = std::fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/bundles/0000000000000000.bundle",
+        ))
+        .times(1)
+        .respond_with(status_code(200).body(bundle)),
+    );
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let url = server.url("/bundles").to_string();
+
+    let cache_dir = concat!(env!("OUT_DIR"), "/should_use_chunk_cache");
+    let _ = std::fs::remove_dir_all(cache_dir);
+    let cache = ChunkCache::open(cache_dir).unwrap();
+    let downloader = Downloader::new(reqwest::Client::new()).with_cache(cache.clone());
+
+    for _ in 0..2 {
+        let mut buf = Vec::new();
+        downloader
+            .download_file(&manifest.data.files[0], &mut buf, url.as_str())
+            .await
+            .unwrap();
+        assert_eq!(buf, b"TEST", "downloaded data did not match");
+    }
+    assert_eq!(cache.len(), 1, "cache should contain 1 chunk");
+}
+
+#[test]
+pub fn should_write_same_manifest_when_parsed() {
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+
+    let bytes = manifest.to_bytes().unwrap();
+    let written = RiotManifest::from_bytes(&bytes, None).unwrap();
+
+    assert_eq!(written.data, manifest.data, "written data did not match");
+    assert_eq!(written.header.manifest_id, manifest.header.manifest_id);
+    assert_eq!(written.header.offset, 28);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn should_round_trip_manifest_through_json() {
+    use rman::ManifestData;
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+
+    let json = serde_json::to_string(&manifest.data).unwrap();
+    let data: ManifestData = serde_json::from_str(&json).unwrap();
+    assert_eq!(data, manifest.data, "data from json did not match");
+
+    let written = RiotManifest {
+        header: manifest.header,
+        data,
+    };
+    let bytes = written.to_bytes().unwrap();
+    let parsed = RiotManifest::from_bytes(&bytes, None).unwrap();
+    assert_eq!(parsed.data, manifest.data, "written manifest did not match");
+
+    let json = serde_json::to_string(&manifest).unwrap();
+    let from_json: RiotManifest = serde_json::from_str(&json).unwrap();
+    assert_eq!(from_json, manifest, "manifest from json did not match");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+pub fn should_serialize_ids_as_hex_when_using_serde_hex() {
+    use rman::entries::ChunkEntry;
+    use rman::{ChunkId, DirectoryId};
+
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Ids {
+        #[serde(with = "rman::serde_hex")]
+        manifest_id: u64,
+        #[serde(with = "rman::serde_hex")]
+        chunk_id: ChunkId,
+        directory_id: DirectoryId,
+    }
+
+    let ids = Ids {
+        manifest_id: 0xFF,
+        chunk_id: ChunkId(-1),
+        directory_id: DirectoryId(1),
+    };
+    let json = serde_json::to_value(&ids).unwrap();
+    assert_eq!(json["manifest_id"], "00000000000000FF");
+    assert_eq!(json["chunk_id"], "FFFFFFFFFFFFFFFF");
+    assert_eq!(json["directory_id"], 1);
+    assert_eq!(serde_json::from_value::<Ids>(json).unwrap(), ids);
+
+    let json = r#"{"manifest_id": "ff", "chunk_id": "1", "directory_id": 2}"#;
+    let parsed: Ids = serde_json::from_str(json).unwrap();
+    assert_eq!(parsed.manifest_id, 0xFF);
+    assert_eq!(parsed.chunk_id, ChunkId(1));
+
+    let json = r#"{"manifest_id": "not hex", "chunk_id": "1", "directory_id": 2}"#;
+    assert!(serde_json::from_str::<Ids>(json).is_err());
+
+    let chunk = ChunkEntry {
+        id: ChunkId(-1),
+        compressed_size: 1,
+        uncompressed_size: 2,
+    };
+    assert_eq!(serde_json::to_value(chunk).unwrap()["id"], -1);
+}
+
+#[test]
+pub fn should_resolve_chunking_param_when_valid_manifest() {
+    use rman::entries::ChunkHashType;
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+
+    let file = &manifest.data.files[0];
+    let chunking_param = manifest.data.chunking_param(file).unwrap();
+    assert_eq!(chunking_param.hash_type(), ChunkHashType::RiotHkdf);
+    assert_eq!(
+        manifest.data.file_entries[0].chunking_param(&manifest.data.chunking_param_entries),
+        Some(chunking_param)
+    );
+
+    assert_eq!(ChunkHashType::from(4), ChunkHashType::Unknown(4));
+    assert_eq!(u8::from(ChunkHashType::Unknown(4)), 4);
+    assert_eq!(u8::from(ChunkHashType::Sha512), 2);
+}
+
+#[cfg(unix)]
+#[tokio::test]
+pub async fn should_apply_permissions_when_downloading_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    use httptest::{matchers::*, responders::*, Expectation, Server};
+    use rman::{Downloader, FilePermissions};
+
+    let bundle = std::fs::read(concat!(env!("OUT_DIR"), "/valid.bundle")).unwrap();
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/bundles/0000000000000000.bundle",
+        ))
+        .times(2)
+        .respond_with(status_code(200).body(bundle)),
+    );
+
+    let path = concat!(env!("OUT_DIR"), "/valid.manifest");
+    let manifest = RiotManifest::from_path(path, None).unwrap();
+    let url = server.url("/bundles").to_string();
+
+    let mut executable = manifest.data.files[0].clone();
+    executable.path = "Test/executable".to_owned();
+    executable.permissions = FilePermissions::EXECUTABLE;
+    let files = [manifest.data.files[0].clone(), executable];
+
+    let root = concat!(env!("OUT_DIR"), "/should_apply_permissions");
+    let _ = std::fs::remove_dir_all(root);
+    Downloader::new(reqwest::Client::new())
+        .download_files(&files, root, url.as_str())
+        .await
+        .unwrap();
+
+    let mode = |path: &str| {
+        let metadata = std::fs::metadata(format!("{root}/{path}")).unwrap();
+        metadata.permissions().mode() & 0o777
+    };
+    assert_eq!(mode("Test/file.txt"), 0o644, "file mode did not match");
+    assert_eq!(
+        mode("Test/executable"),
+        0o755,
+        "executable mode did not match"
+    );
+}